@@ -3,7 +3,10 @@ use std::fs;
 use std::path::Path;
 
 fn main() {
-    copy_dlls().unwrap();
+    // 只有启用steam功能时才需要Steam的动态库
+    if env::var_os("CARGO_FEATURE_STEAM").is_some() {
+        copy_dlls().unwrap();
+    }
 
     println!("cargo:rerun-if-changed=build.rs");
 }
@@ -0,0 +1,330 @@
+use crate::data::TableData;
+use crate::page::Page;
+use crate::{Color32, MyApp, RichText, ScrollArea, SidePanel, TextEdit, Vec2, egui};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const IMAGE_DIR: &str = "Image_Mod";
+const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+pub const THUMBNAIL_SIZE: f32 = 64.0;
+
+/// Image_Mod 中的一张图片，`name` 为不带扩展名的文件名
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageAsset {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl ImageAsset {
+    pub fn uri(&self) -> String {
+        format!("file://{}", self.path.display())
+    }
+
+    pub fn thumbnail(&self, size: f32) -> egui::Image<'static> {
+        egui::Image::new(self.uri())
+            .fit_to_exact_size(Vec2::splat(size))
+            .maintain_aspect_ratio(true)
+    }
+}
+
+/// 当前模组 Image_Mod 目录的索引
+#[derive(Default)]
+pub struct ImageIndex {
+    dir: Option<PathBuf>,
+    pub images: Vec<ImageAsset>,
+}
+
+impl ImageIndex {
+    /// 工作区变化时重新索引
+    pub fn sync(&mut self, workspace: Option<&str>) {
+        let dir = workspace.map(|x| Path::new(x).join(IMAGE_DIR));
+        if dir != self.dir {
+            self.dir = dir;
+            self.refresh();
+        }
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    pub fn refresh(&mut self) {
+        self.images = self.dir.as_deref().map(index_images).unwrap_or_default();
+    }
+
+    /// 按图标名查找，图标名可以带扩展名
+    pub fn find(&self, icon: &str) -> Option<&ImageAsset> {
+        let icon = icon.trim();
+        self.images
+            .iter()
+            .find(|x| x.name == icon || x.path.file_name().is_some_and(|name| name == icon))
+    }
+
+    /// 填写了图标但 Image_Mod 中没有对应文件
+    pub fn is_missing(&self, icon: &str) -> bool {
+        self.dir.is_some() && !icon.trim().is_empty() && self.find(icon).is_none()
+    }
+
+    /// 把png复制到 Image_Mod，已存在的同名文件不会被覆盖，返回提示信息
+    pub fn import(&mut self, files: &[PathBuf]) -> Vec<String> {
+        let Some(dir) = self.dir.clone() else {
+            return vec!["请先选择工作区".to_string()];
+        };
+        let mut messages = Vec::new();
+        if let Err(e) = fs::create_dir_all(&dir) {
+            messages.push(format!("{}: {}", dir.display(), e));
+            return messages;
+        }
+        for file in files {
+            let is_png = file
+                .extension()
+                .is_some_and(|x| x.eq_ignore_ascii_case("png"));
+            let Some(name) = file.file_name().filter(|_| is_png) else {
+                messages.push(format!("只能导入png图片: {}", file.display()));
+                continue;
+            };
+            let target = dir.join(name);
+            if target.exists() {
+                messages.push(format!("图片已存在: {}", target.display()));
+                continue;
+            }
+            if let Err(e) = fs::copy(file, &target) {
+                messages.push(format!("{}: {}", file.display(), e));
+            }
+        }
+        self.refresh();
+        messages
+    }
+}
+
+/// 列出目录下的图片，按名称排序
+fn index_images(dir: &Path) -> Vec<ImageAsset> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut images: Vec<ImageAsset> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+            if !path.is_file() || !IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+                return None;
+            }
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some(ImageAsset { name, path })
+        })
+        .collect();
+    images.sort_by(|a, b| a.name.cmp(&b.name));
+    images
+}
+
+/// 表中引用图标的一个字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct IconRef {
+    pub page: Page,
+    pub index: usize,
+    pub row_id: i32,
+    pub field: &'static str,
+    pub icon: String,
+}
+
+/// 遍历所有表中填写了的图标字段
+pub fn icon_references(table_data: &TableData) -> Vec<IconRef> {
+    let mut refs = Vec::new();
+    let mut push = |page: &Page, index, row_id, field, icon: &String| {
+        if !icon.trim().is_empty() {
+            refs.push(IconRef {
+                page: page.clone(),
+                index,
+                row_id,
+                field,
+                icon: icon.clone(),
+            });
+        }
+    };
+    for (i, x) in table_data.relics.iter().enumerate() {
+        push(&Page::Relic, i, x.id, "Icon", &x.icon);
+        push(&Page::Relic, i, x.id, "SmallIcon", &x.small_icon);
+    }
+    for (i, x) in table_data.elements.iter().enumerate() {
+        push(&Page::Element, i, x.id, "Icon", &x.icon);
+    }
+    for (i, x) in table_data.enemies.iter().enumerate() {
+        push(&Page::Enemy, i, x.id, "Icon", &x.icon);
+    }
+    for (i, x) in table_data.race_attributes.iter().enumerate() {
+        push(&Page::RaceAttribute, i, x.id, "Icon", &x.icon);
+    }
+    refs
+}
+
+#[derive(Default)]
+pub struct AssetWindowState {
+    pub open: bool,
+    search: String,
+    selected: Option<usize>,
+}
+
+pub fn render_asset_window(app: &mut MyApp, ctx: &egui::Context) {
+    let mut open = app.app_state.asset_window.open;
+    if !open {
+        return;
+    }
+
+    // 拖入的文件导入到 Image_Mod
+    let dropped: Vec<PathBuf> = ctx.input(|i| {
+        i.raw
+            .dropped_files
+            .iter()
+            .filter_map(|x| x.path.clone())
+            .collect()
+    });
+    if !dropped.is_empty() {
+        let messages = app.app_state.images.import(&dropped);
+        app.app_state.messages.extend(messages);
+    }
+
+    let mut jump = None;
+    let mut refresh = false;
+    egui::Window::new("图片资源")
+        .open(&mut open)
+        .default_size([720.0, 480.0])
+        .show(ctx, |ui| {
+            let state = &mut app.app_state.asset_window;
+            let images = &app.app_state.images;
+            let Some(dir) = images.dir() else {
+                ui.label("请先选择工作区");
+                return;
+            };
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} · {} 张图片",
+                    dir.display(),
+                    images.images.len()
+                ));
+                refresh = ui.button("刷新").clicked();
+                ui.add(
+                    TextEdit::singleline(&mut state.search)
+                        .hint_text("搜索")
+                        .desired_width(140.0),
+                );
+            });
+            ui.label(RichText::new("把png拖到窗口中即可导入").weak());
+            ui.separator();
+
+            let missing: Vec<IconRef> = icon_references(&app.app_state.table_data)
+                .into_iter()
+                .filter(|x| images.is_missing(&x.icon))
+                .collect();
+            SidePanel::right("asset_missing")
+                .resizable(true)
+                .default_width(220.0)
+                .show_inside(ui, |ui| {
+                    ui.colored_label(
+                        if missing.is_empty() {
+                            ui.visuals().text_color()
+                        } else {
+                            Color32::RED
+                        },
+                        format!("缺少图片 {}", missing.len()),
+                    );
+                    ui.separator();
+                    ScrollArea::vertical().show(ui, |ui| {
+                        for r in &missing {
+                            let text = format!(
+                                "{} #{} {}: {}",
+                                r.page.get_info().title,
+                                r.row_id,
+                                r.field,
+                                r.icon
+                            );
+                            if ui.link(text).clicked() {
+                                jump = Some((r.page.clone(), r.index));
+                            }
+                        }
+                    });
+                });
+
+            egui::CentralPanel::default().show_inside(ui, |ui| {
+                let search = state.search.trim().to_lowercase();
+                ScrollArea::vertical().show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for (i, image) in images.images.iter().enumerate() {
+                            if !image.name.to_lowercase().contains(&search) {
+                                continue;
+                            }
+                            let response = thumbnail_button(ui, image, state.selected == Some(i));
+                            if response.clicked() {
+                                state.selected = Some(i);
+                                ui.ctx().copy_text(image.name.clone());
+                            }
+                        }
+                    });
+                });
+            });
+        });
+    app.app_state.asset_window.open = open;
+
+    if refresh {
+        ctx.forget_all_images();
+        app.app_state.images.refresh();
+    }
+    if let Some((page, index)) = jump {
+        app.app_data.selected_page = Some(page);
+        app.app_state.scroll_to_row = Some(index);
+    }
+}
+
+/// 带名称的缩略图按钮
+pub fn thumbnail_button(ui: &mut egui::Ui, image: &ImageAsset, selected: bool) -> egui::Response {
+    ui.vertical(|ui| {
+        ui.set_width(THUMBNAIL_SIZE + 8.0);
+        let response = ui
+            .add(egui::Button::image(image.thumbnail(THUMBNAIL_SIZE)).selected(selected))
+            .on_hover_text(image.path.display().to_string());
+        ui.add(egui::Label::new(RichText::new(&image.name).small()).truncate());
+        response
+    })
+    .inner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    fn workspace() -> TempDir {
+        let root = TempDir::new("assets");
+        let images = root.join(IMAGE_DIR);
+        fs::create_dir_all(&images).unwrap();
+        fs::write(images.join("Cat.png"), b"").unwrap();
+        fs::write(images.join("notes.txt"), b"").unwrap();
+        fs::write(root.join("Dog.png"), b"").unwrap();
+        fs::write(root.join("Dog.gif"), b"").unwrap();
+        root
+    }
+
+    #[test]
+    fn index_finds_png_icons_with_or_without_extension() {
+        let root = workspace();
+        let mut index = ImageIndex::default();
+        assert!(!index.is_missing("Cat"));
+        index.sync(root.path().to_str());
+        assert_eq!(index.images.len(), 1);
+        assert!(index.find("Cat").is_some());
+        assert!(index.find("Cat.png").is_some());
+        assert!(index.is_missing("Dog"));
+        assert!(!index.is_missing(""));
+    }
+
+    #[test]
+    fn import_copies_png_only_and_keeps_existing_files() {
+        let root = workspace();
+        let mut index = ImageIndex::default();
+        index.sync(root.path().to_str());
+        let messages = index.import(&[root.join("Dog.png"), root.join("Dog.gif")]);
+        assert_eq!(messages.len(), 1);
+        assert!(!index.is_missing("Dog"));
+        // 同名文件不覆盖
+        assert_eq!(index.import(&[root.join("Dog.png")]).len(), 1);
+    }
+}
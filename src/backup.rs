@@ -0,0 +1,273 @@
+use crate::data::{TableData, editor_dir, read_backup};
+use crate::history::{TableEdit, diff_tables};
+use crate::page::Page;
+use crate::{ComboBox, MyApp, RichText, SIDEBAR_PAGES, ScrollArea, SidePanel, egui};
+use chrono::{Local, NaiveDateTime};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 每张表保留的备份数量
+pub const MAX_BACKUPS: usize = 10;
+
+const BACKUP_DIR: &str = "Backup";
+const TIME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+/// 一份备份文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupFile {
+    pub path: PathBuf,
+    pub time: NaiveDateTime,
+}
+
+/// 表的备份目录，位于工作区之外的 Backup/<表名> 下
+pub fn backup_dir(workspace: &Path, page: &Page) -> PathBuf {
+    let name = crate::data::json_file_name(page);
+    editor_dir(workspace)
+        .join(BACKUP_DIR)
+        .join(name.trim_end_matches(".json"))
+}
+
+/// 把即将被覆盖的文件复制为带时间戳的备份，并删掉多余的旧备份
+pub fn backup_table(workspace: &Path, page: &Page, file: &Path) -> io::Result<PathBuf> {
+    let dir = backup_dir(workspace, page);
+    fs::create_dir_all(&dir)?;
+    let stamp = Local::now().naive_local().format(TIME_FORMAT).to_string();
+    let stamp = stamp.replace('.', "-");
+    // 同一毫秒内的多次备份加上序号，不覆盖之前的备份
+    let (target, mut backup) = (0..)
+        .map(|i| match i {
+            0 => dir.join(format!("{}.json", stamp)),
+            i => dir.join(format!("{}_{}.json", stamp, i)),
+        })
+        .find_map(|path| {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(backup) => Some(Ok((path, backup))),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => None,
+                Err(e) => Some(Err(e)),
+            }
+        })
+        .unwrap()?;
+    io::copy(&mut fs::File::open(file)?, &mut backup)?;
+    for old in list_backups(workspace, page).into_iter().skip(MAX_BACKUPS) {
+        fs::remove_file(old.path)?;
+    }
+    Ok(target)
+}
+
+/// 列出表的备份，新的在前
+pub fn list_backups(workspace: &Path, page: &Page) -> Vec<BackupFile> {
+    let Ok(entries) = fs::read_dir(backup_dir(workspace, page)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<BackupFile> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let stem = path.file_stem()?.to_str()?;
+            // 去掉同一毫秒内的序号，毫秒前的点换成了横线
+            let stem = stem.split_once('_').map_or(stem, |x| x.0);
+            let (time, millis) = stem.rsplit_once('-')?;
+            let time =
+                NaiveDateTime::parse_from_str(&format!("{}.{}", time, millis), TIME_FORMAT).ok()?;
+            Some(BackupFile { path, time })
+        })
+        .collect();
+    backups.sort_by(|a, b| {
+        let seq = |x: &BackupFile| {
+            let stem = x.path.file_stem()?.to_str()?;
+            stem.split_once('_')?.1.parse::<u32>().ok()
+        };
+        b.time.cmp(&a.time).then(seq(b).cmp(&seq(a)))
+    });
+    backups
+}
+
+#[derive(Default)]
+pub struct BackupWindowState {
+    pub open: bool,
+    page: Page,
+    backups: Vec<BackupFile>,
+    selected: Option<usize>,
+    preview: Option<(TableData, Vec<TableEdit>)>,
+}
+
+impl BackupWindowState {
+    fn refresh(&mut self, workspace: Option<&str>) {
+        self.backups = workspace
+            .map(|x| list_backups(Path::new(x), &self.page))
+            .unwrap_or_default();
+        self.selected = None;
+        self.preview = None;
+    }
+}
+
+pub fn render_backup_window(app: &mut MyApp, ctx: &egui::Context) {
+    let mut open = app.app_state.backup_window.open;
+    if !open {
+        return;
+    }
+    let mut select = None;
+    let mut restore = false;
+    let mut refresh = false;
+    egui::Window::new("恢复备份")
+        .open(&mut open)
+        .default_size([640.0, 420.0])
+        .show(ctx, |ui| {
+            let state = &mut app.app_state.backup_window;
+            ui.horizontal(|ui| {
+                ui.label("表:");
+                ComboBox::from_id_salt("backup_page")
+                    .selected_text(state.page.get_info().title)
+                    .show_ui(ui, |ui| {
+                        for page in SIDEBAR_PAGES {
+                            refresh |= ui
+                                .selectable_value(
+                                    &mut state.page,
+                                    page.clone(),
+                                    page.get_info().title,
+                                )
+                                .changed();
+                        }
+                    });
+                refresh |= ui.button("刷新").clicked();
+            });
+            ui.separator();
+
+            SidePanel::left("backup_list")
+                .resizable(true)
+                .default_width(200.0)
+                .show_inside(ui, |ui| {
+                    if state.backups.is_empty() {
+                        ui.label("没有备份");
+                    }
+                    ScrollArea::vertical().show(ui, |ui| {
+                        for (i, backup) in state.backups.iter().enumerate() {
+                            let text = backup.time.format("%Y-%m-%d %H:%M:%S").to_string();
+                            if ui
+                                .selectable_label(state.selected == Some(i), text)
+                                .clicked()
+                            {
+                                select = Some(i);
+                            }
+                        }
+                    });
+                });
+
+            egui::CentralPanel::default().show_inside(ui, |ui| {
+                let Some((_, edits)) = &state.preview else {
+                    ui.label("请选择一份备份");
+                    return;
+                };
+                ui.horizontal(|ui| {
+                    ui.label(format!("恢复后共 {} 处变化", edits.len()));
+                    if ui
+                        .add_enabled(!edits.is_empty(), egui::Button::new("恢复"))
+                        .clicked()
+                    {
+                        restore = true;
+                    }
+                });
+                ui.separator();
+                ScrollArea::vertical().show(ui, |ui| {
+                    if edits.is_empty() {
+                        ui.label(RichText::new("与当前数据相同").weak());
+                    }
+                    for edit in edits {
+                        ui.label(edit.describe());
+                    }
+                });
+            });
+        });
+    app.app_state.backup_window.open = open;
+
+    let workspace = app.get_current_workspace().map(str::to_string);
+    if refresh {
+        app.app_state.backup_window.refresh(workspace.as_deref());
+    }
+    if let Some(i) = select {
+        let state = &app.app_state.backup_window;
+        let (page, path) = (state.page.clone(), state.backups[i].path.clone());
+        let preview = read_backup(app, &page, &path).map(|data| {
+            let edits = diff_tables(&app.app_state.table_data, &data);
+            (data, edits)
+        });
+        let state = &mut app.app_state.backup_window;
+        state.selected = Some(i);
+        state.preview = preview;
+    }
+    if restore && let Some((data, _)) = app.app_state.backup_window.preview.take() {
+        // 恢复只改编辑器里的数据，可以撤销，保存后才写入文件
+        app.app_state.table_data = data;
+        app.app_state.backup_window.selected = None;
+        app.app_state
+            .messages
+            .push_back("已恢复备份，保存后生效".to_string());
+    }
+}
+
+/// 打开窗口并读取当前页面对应表的备份
+pub fn open_backup_window(app: &mut MyApp) {
+    let page = app.app_data.selected_page.clone().unwrap_or_default();
+    let workspace = app.get_current_workspace().map(str::to_string);
+    let state = &mut app.app_state.backup_window;
+    state.open = true;
+    state.page = page;
+    state.refresh(workspace.as_deref());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    /// 工作区是临时目录的子目录，编辑器目录也在临时目录中
+    fn workspace() -> (TempDir, PathBuf, PathBuf) {
+        let dir = TempDir::new("backup");
+        let workspace = dir.join("mod");
+        fs::create_dir_all(&workspace).unwrap();
+        let file = workspace.join("relics_mod.json");
+        (dir, workspace, file)
+    }
+
+    #[test]
+    fn backups_rotate_and_list_newest_first() {
+        let (_dir, workspace, file) = workspace();
+        for i in 0..MAX_BACKUPS + 2 {
+            fs::write(&file, i.to_string()).unwrap();
+            backup_table(&workspace, &Page::Relic, &file).unwrap();
+        }
+
+        let backups = list_backups(&workspace, &Page::Relic);
+        assert_eq!(backups.len(), MAX_BACKUPS);
+        let newest = fs::read_to_string(&backups[0].path).unwrap();
+        let oldest = fs::read_to_string(&backups[MAX_BACKUPS - 1].path).unwrap();
+        assert_eq!(newest, (MAX_BACKUPS + 1).to_string());
+        assert_eq!(oldest, "2");
+        assert!(list_backups(&workspace, &Page::Enemy).is_empty());
+    }
+
+    #[test]
+    fn backups_in_the_same_millisecond_are_kept() {
+        let (_dir, workspace, file) = workspace();
+        fs::write(&file, "a").unwrap();
+        let first = backup_table(&workspace, &Page::Relic, &file).unwrap();
+        fs::write(&file, "b").unwrap();
+        let second = backup_table(&workspace, &Page::Relic, &file).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(first).unwrap(), "a");
+        assert_eq!(fs::read_to_string(second).unwrap(), "b");
+    }
+
+    #[test]
+    fn backups_are_outside_the_workspace() {
+        let (_dir, workspace, file) = workspace();
+        fs::write(&file, "a").unwrap();
+        let backup = backup_table(&workspace, &Page::Relic, &file).unwrap();
+        assert!(!backup.starts_with(&workspace));
+        assert_eq!(fs::read_dir(&workspace).unwrap().count(), 1);
+    }
+}
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::ffi::CString;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ElementAttribute {
     #[serde(rename = "Id")]
     pub id: i32,
@@ -10,7 +10,7 @@ pub struct ElementAttribute {
     pub value: i32,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ElementTriggerValue {
     #[serde(rename = "Value")]
     pub value: Vec<i32>,
@@ -33,6 +33,21 @@ pub struct ModData
     pub desc: String,
     #[serde(rename = "Version")]
     pub version : String,
+    /// 创意工坊物品id，第一次上传成功后写入
+    #[serde(rename = "ModId", default, skip_serializing_if = "Option::is_none")]
+    pub mod_id: Option<u64>,
+}
+
+/// Sound_Mod 中注册的音效
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SoundEntry {
+    #[serde(rename = "Id")]
+    pub id: i32,
+    /// Sound_Mod 中的文件名
+    #[serde(rename = "File")]
+    pub file: String,
+    #[serde(rename = "Type")]
+    pub audio_type: AudioType,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -73,6 +88,15 @@ pub enum ERare {
     Mythic = 5,
 }
 
+/// 音频格式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AudioType {
+    #[default]
+    Unknown = 0,
+    Acc = 1,
+    Aiff = 2,
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Etip {
     #[default]
@@ -84,7 +108,7 @@ pub enum Etip {
     Near = 5,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Element {
     /// 这是id
     #[serde(rename = "Id")]
@@ -152,7 +176,7 @@ pub struct Element {
     pub attack_particle: i32,
 }
 
-#[derive(Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Enemy {
     /// 这是id
     #[serde(rename = "Id")]
@@ -207,7 +231,7 @@ pub struct Enemy {
     pub show_sound: i32,
 }
 
-#[derive(Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Localization {
     /// 这是id
     #[serde(rename = "Id")]
@@ -222,7 +246,7 @@ pub struct Localization {
     pub cnt: String,
 }
 
-#[derive(Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct RaceAttribute {
     /// 这是id
     #[serde(rename = "Id")]
@@ -238,7 +262,7 @@ pub struct RaceAttribute {
     pub desc_id: i32,
 }
 
-#[derive(Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Relics {
     /// 这是id
     #[serde(rename = "Id")]
@@ -299,6 +323,75 @@ pub struct Relics {
     pub other_value: Vec<i32>,
 }
 
+impl ERaceType {
+    pub const ALL: [ERaceType; 22] = [
+        ERaceType::None,
+        ERaceType::Item,
+        ERaceType::Cat,
+        ERaceType::Dog,
+        ERaceType::Bird,
+        ERaceType::Bug,
+        ERaceType::Fish,
+        ERaceType::Dragon,
+        ERaceType::Snake,
+        ERaceType::Hoofed,
+        ERaceType::Lizard,
+        ERaceType::Rodents,
+        ERaceType::Molluscs,
+        ERaceType::Furry,
+        ERaceType::Mod1,
+        ERaceType::Mod2,
+        ERaceType::Mod3,
+        ERaceType::Mod4,
+        ERaceType::Mod5,
+        ERaceType::Mod6,
+        ERaceType::Mod7,
+        ERaceType::Mod8,
+    ];
+
+    /// 是否为留给模组的种族
+    pub fn is_mod_slot(&self) -> bool {
+        *self as i32 >= ERaceType::Mod1 as i32
+    }
+}
+
+impl ERare {
+    pub const ALL: [ERare; 6] = [
+        ERare::None,
+        ERare::Common,
+        ERare::Rare,
+        ERare::Legend,
+        ERare::Epic,
+        ERare::Mythic,
+    ];
+
+    pub fn from_i32(value: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| *x as i32 == value)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ERare::None => "无",
+            ERare::Common => "普通",
+            ERare::Rare => "稀有",
+            ERare::Legend => "传说",
+            ERare::Epic => "史诗",
+            ERare::Mythic => "神话",
+        }
+    }
+}
+
+impl Etip {
+    pub const ALL: [Etip; 6] = [
+        Etip::None,
+        Etip::Sum,
+        Etip::Before,
+        Etip::Prob,
+        Etip::SumEnd,
+        Etip::Near,
+    ];
+}
+
 impl Ord for Relics {
     fn cmp(&self, other: &Self) -> Ordering {
         self.id.cmp(&other.id)
@@ -366,3 +459,470 @@ impl PartialOrd for RaceAttribute {
         Some(self.cmp(other))
     }
 }
+
+/// 按主键合并的表格行
+pub trait TableRow {
+    fn id(&self) -> i32;
+}
+
+impl TableRow for Relics {
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl TableRow for SoundEntry {
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl TableRow for Element {
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl TableRow for Enemy {
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl TableRow for Localization {
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl TableRow for RaceAttribute {
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+/// 逐字段比较表格行的内容，用于撤销记录和未保存标记
+pub trait SameRow {
+    fn same_row(&self, other: &Self) -> bool;
+}
+
+impl SameRow for Relics {
+    fn same_row(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl SameRow for Element {
+    /// Element 的相等只比较 id，这里需要比较全部字段
+    fn same_row(&self, other: &Self) -> bool {
+        let Element {
+            id,
+            lock,
+            enable,
+            role,
+            name_id,
+            desc_id,
+            desctip,
+            desc_attribute,
+            icon,
+            rare,
+            race_type,
+            other_race,
+            attribute,
+            trigger_type,
+            trigger_param,
+            event_tip,
+            trigger_action,
+            trigger_value,
+            other_value,
+            attack_sound,
+            select_sound,
+            attack_particle,
+        } = self;
+        *id == other.id
+            && *lock == other.lock
+            && *enable == other.enable
+            && *role == other.role
+            && *name_id == other.name_id
+            && *desc_id == other.desc_id
+            && *desctip == other.desctip
+            && *desc_attribute == other.desc_attribute
+            && *icon == other.icon
+            && *rare == other.rare
+            && *race_type == other.race_type
+            && *other_race == other.other_race
+            && *attribute == other.attribute
+            && *trigger_type == other.trigger_type
+            && *trigger_param == other.trigger_param
+            && *event_tip == other.event_tip
+            && *trigger_action == other.trigger_action
+            && *trigger_value == other.trigger_value
+            && *other_value == other.other_value
+            && *attack_sound == other.attack_sound
+            && *select_sound == other.select_sound
+            && *attack_particle == other.attack_particle
+    }
+}
+
+impl SameRow for Enemy {
+    fn same_row(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl SameRow for Localization {
+    fn same_row(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl SameRow for RaceAttribute {
+    fn same_row(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// 比较两张表的内容是否完全相同
+pub fn same_rows<T: SameRow>(a: &[T], b: &[T]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same_row(b))
+}
+
+impl From<&cfg::element::ERaceType> for ERaceType {
+    fn from(value: &cfg::element::ERaceType) -> Self {
+        use cfg::element::ERaceType as E;
+        match value {
+            E::None => ERaceType::None,
+            E::Item => ERaceType::Item,
+            E::Cat => ERaceType::Cat,
+            E::Dog => ERaceType::Dog,
+            E::Bird => ERaceType::Bird,
+            E::Bug => ERaceType::Bug,
+            E::Fish => ERaceType::Fish,
+            E::Dragon => ERaceType::Dragon,
+            E::Snake => ERaceType::Snake,
+            E::Hoofed => ERaceType::Hoofed,
+            E::Lizard => ERaceType::Lizard,
+            E::Rodents => ERaceType::Rodents,
+            E::Molluscs => ERaceType::Molluscs,
+            E::Furry => ERaceType::Furry,
+            E::Mod1 => ERaceType::Mod1,
+            E::Mod2 => ERaceType::Mod2,
+            E::Mod3 => ERaceType::Mod3,
+            E::Mod4 => ERaceType::Mod4,
+            E::Mod5 => ERaceType::Mod5,
+            E::Mod6 => ERaceType::Mod6,
+            E::Mod7 => ERaceType::Mod7,
+            E::Mod8 => ERaceType::Mod8,
+        }
+    }
+}
+
+impl From<&cfg::AudioType> for AudioType {
+    fn from(value: &cfg::AudioType) -> Self {
+        match value {
+            cfg::AudioType::UNKNOWN => AudioType::Unknown,
+            cfg::AudioType::ACC => AudioType::Acc,
+            cfg::AudioType::AIFF => AudioType::Aiff,
+        }
+    }
+}
+
+impl From<&cfg::element::Etip> for Etip {
+    fn from(value: &cfg::element::Etip) -> Self {
+        use cfg::element::Etip as E;
+        match value {
+            E::None => Etip::None,
+            E::Sum => Etip::Sum,
+            E::Before => Etip::Before,
+            E::Prob => Etip::Prob,
+            E::SumEnd => Etip::SumEnd,
+            E::Near => Etip::Near,
+        }
+    }
+}
+
+impl From<&cfg::ElementAttribute> for ElementAttribute {
+    fn from(value: &cfg::ElementAttribute) -> Self {
+        Self {
+            id: value.ID,
+            value: value.Value,
+        }
+    }
+}
+
+impl From<&cfg::ElementTriggerValue> for ElementTriggerValue {
+    fn from(value: &cfg::ElementTriggerValue) -> Self {
+        Self {
+            value: value.Value.clone(),
+        }
+    }
+}
+
+impl From<&cfg::element::Element> for Element {
+    fn from(value: &cfg::element::Element) -> Self {
+        Self {
+            id: value.Id,
+            lock: value.Lock,
+            enable: value.Enable,
+            role: value.Role,
+            name_id: value.name_id,
+            desc_id: value.desc_id,
+            desctip: value.desctip.iter().map(Etip::from).collect(),
+            desc_attribute: value.DescAttribute.clone(),
+            icon: value.Icon.clone(),
+            rare: value.Rare,
+            race_type: (&value.RaceType).into(),
+            other_race: (&value.OtherRace).into(),
+            attribute: value.Attribute.iter().map(ElementAttribute::from).collect(),
+            trigger_type: value.TriggerType,
+            trigger_param: value.TriggerParam.clone(),
+            event_tip: value.EventTip,
+            trigger_action: value.TriggerAction,
+            trigger_value: value
+                .TriggerValue
+                .iter()
+                .map(ElementTriggerValue::from)
+                .collect(),
+            other_value: value.OtherValue.clone(),
+            attack_sound: value.AttackSound,
+            select_sound: value.SelectSound,
+            attack_particle: value.AttackParticle,
+        }
+    }
+}
+
+impl From<&cfg::enemy::Enemy> for Enemy {
+    fn from(value: &cfg::enemy::Enemy) -> Self {
+        Self {
+            id: value.Id,
+            icon: value.Icon.clone(),
+            name_id: value.NameID,
+            desc_id: value.DescID,
+            map: value.Map,
+            level: value.Level,
+            boss: value.BOSS,
+            hp: value.Hp,
+            attack: value.Attack,
+            upgrade_attack: value.UpgradeAttack,
+            endless_hp: value.EndlessHp,
+            endless_attack: value.EndlessAttack,
+            endless_upgrade: value.EndlessUpgrade,
+            gold: value.Gold,
+            gold_max: value.GoldMax,
+            attack_sound: value.AttackSound,
+            show_sound: value.ShowSound,
+        }
+    }
+}
+
+impl From<&cfg::localization::Localization> for Localization {
+    fn from(value: &cfg::localization::Localization) -> Self {
+        Self {
+            id: value.Id,
+            cn: value.CN.clone(),
+            en: value.EN.clone(),
+            jp: value.JP.clone(),
+            cnt: value.CNT.clone(),
+        }
+    }
+}
+
+impl From<&cfg::raceattribute::RaceAttribute> for RaceAttribute {
+    fn from(value: &cfg::raceattribute::RaceAttribute) -> Self {
+        Self {
+            id: value.Id,
+            icon: value.Icon.clone(),
+            name_id: value.NameID,
+            desc_id: value.DescID,
+        }
+    }
+}
+
+impl From<&cfg::relics::Relics> for Relics {
+    fn from(value: &cfg::relics::Relics) -> Self {
+        Self {
+            id: value.Id,
+            enable: value.Enable,
+            lock: value.Lock,
+            role: value.role,
+            tutorial: value.tutorial,
+            icon: value.Icon.clone(),
+            small_icon: value.SmallIcon.clone(),
+            name_id: value.NameID,
+            desc_id: value.DescID,
+            other_desc_id: value.OtherDescID,
+            desc_tip: value.DescTip.iter().map(Etip::from).collect(),
+            rare: value.Rare,
+            passive: value.Passive.clone(),
+            trigger_type: value.TriggerType,
+            trigger_param: value.TriggerParam.clone(),
+            event_tip: value.EventTip,
+            trigger_action: value.TriggerAction,
+            trigger_value: value.TriggerValue.clone(),
+            other_value: value.OtherValue.clone(),
+        }
+    }
+}
+
+impl From<&ERaceType> for cfg::element::ERaceType {
+    fn from(value: &ERaceType) -> Self {
+        use cfg::element::ERaceType as E;
+        match value {
+            ERaceType::None => E::None,
+            ERaceType::Item => E::Item,
+            ERaceType::Cat => E::Cat,
+            ERaceType::Dog => E::Dog,
+            ERaceType::Bird => E::Bird,
+            ERaceType::Bug => E::Bug,
+            ERaceType::Fish => E::Fish,
+            ERaceType::Dragon => E::Dragon,
+            ERaceType::Snake => E::Snake,
+            ERaceType::Hoofed => E::Hoofed,
+            ERaceType::Lizard => E::Lizard,
+            ERaceType::Rodents => E::Rodents,
+            ERaceType::Molluscs => E::Molluscs,
+            ERaceType::Furry => E::Furry,
+            ERaceType::Mod1 => E::Mod1,
+            ERaceType::Mod2 => E::Mod2,
+            ERaceType::Mod3 => E::Mod3,
+            ERaceType::Mod4 => E::Mod4,
+            ERaceType::Mod5 => E::Mod5,
+            ERaceType::Mod6 => E::Mod6,
+            ERaceType::Mod7 => E::Mod7,
+            ERaceType::Mod8 => E::Mod8,
+        }
+    }
+}
+
+impl From<&Etip> for cfg::element::Etip {
+    fn from(value: &Etip) -> Self {
+        use cfg::element::Etip as E;
+        match value {
+            Etip::None => E::None,
+            Etip::Sum => E::Sum,
+            Etip::Before => E::Before,
+            Etip::Prob => E::Prob,
+            Etip::SumEnd => E::SumEnd,
+            Etip::Near => E::Near,
+        }
+    }
+}
+
+impl From<&ElementAttribute> for cfg::ElementAttribute {
+    fn from(value: &ElementAttribute) -> Self {
+        Self {
+            ID: value.id,
+            Value: value.value,
+        }
+    }
+}
+
+impl From<&ElementTriggerValue> for cfg::ElementTriggerValue {
+    fn from(value: &ElementTriggerValue) -> Self {
+        Self {
+            Value: value.value.clone(),
+        }
+    }
+}
+
+impl From<&Element> for cfg::element::Element {
+    fn from(value: &Element) -> Self {
+        Self {
+            Id: value.id,
+            Lock: value.lock,
+            Enable: value.enable,
+            Role: value.role,
+            name_id: value.name_id,
+            desc_id: value.desc_id,
+            desctip: value.desctip.iter().map(Into::into).collect(),
+            DescAttribute: value.desc_attribute.clone(),
+            Icon: value.icon.clone(),
+            Rare: value.rare,
+            RaceType: (&value.race_type).into(),
+            OtherRace: (&value.other_race).into(),
+            Attribute: value.attribute.iter().map(Into::into).collect(),
+            TriggerType: value.trigger_type,
+            TriggerParam: value.trigger_param.clone(),
+            EventTip: value.event_tip,
+            TriggerAction: value.trigger_action,
+            TriggerValue: value.trigger_value.iter().map(Into::into).collect(),
+            OtherValue: value.other_value.clone(),
+            AttackSound: value.attack_sound,
+            SelectSound: value.select_sound,
+            AttackParticle: value.attack_particle,
+        }
+    }
+}
+
+impl From<&Enemy> for cfg::enemy::Enemy {
+    fn from(value: &Enemy) -> Self {
+        Self {
+            Id: value.id,
+            Icon: value.icon.clone(),
+            NameID: value.name_id,
+            DescID: value.desc_id,
+            Map: value.map,
+            Level: value.level,
+            BOSS: value.boss,
+            Hp: value.hp,
+            Attack: value.attack,
+            UpgradeAttack: value.upgrade_attack,
+            EndlessHp: value.endless_hp,
+            EndlessAttack: value.endless_attack,
+            EndlessUpgrade: value.endless_upgrade,
+            Gold: value.gold,
+            GoldMax: value.gold_max,
+            AttackSound: value.attack_sound,
+            ShowSound: value.show_sound,
+        }
+    }
+}
+
+impl From<&Localization> for cfg::localization::Localization {
+    fn from(value: &Localization) -> Self {
+        Self {
+            Id: value.id,
+            CN: value.cn.clone(),
+            EN: value.en.clone(),
+            JP: value.jp.clone(),
+            CNT: value.cnt.clone(),
+        }
+    }
+}
+
+impl From<&RaceAttribute> for cfg::raceattribute::RaceAttribute {
+    fn from(value: &RaceAttribute) -> Self {
+        Self {
+            Id: value.id,
+            Icon: value.icon.clone(),
+            NameID: value.name_id,
+            DescID: value.desc_id,
+        }
+    }
+}
+
+impl From<&Relics> for cfg::relics::Relics {
+    fn from(value: &Relics) -> Self {
+        Self {
+            Id: value.id,
+            Enable: value.enable,
+            Lock: value.lock,
+            role: value.role,
+            tutorial: value.tutorial,
+            Icon: value.icon.clone(),
+            SmallIcon: value.small_icon.clone(),
+            NameID: value.name_id,
+            DescID: value.desc_id,
+            OtherDescID: value.other_desc_id,
+            DescTip: value.desc_tip.iter().map(Into::into).collect(),
+            Rare: value.rare,
+            Passive: value.passive.clone(),
+            TriggerType: value.trigger_type,
+            TriggerParam: value.trigger_param.clone(),
+            EventTip: value.event_tip,
+            TriggerAction: value.trigger_action,
+            TriggerValue: value.trigger_value.clone(),
+            OtherValue: value.other_value.clone(),
+        }
+    }
+}
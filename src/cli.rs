@@ -0,0 +1,414 @@
+use crate::SIDEBAR_PAGES;
+use crate::assets::{ImageIndex, icon_references};
+use crate::data::{
+    TableData, data_dir, json_file_name, load_table_data, write_luban_bytes, write_table_json,
+};
+use crate::history::diff_tables;
+use crate::page::Page;
+use crate::preflight::check_mod_folder;
+use crate::sound::SoundRegistry;
+use crate::uploadscreen::MOD_DATA_FILE;
+use crate::validation::{Issue, Severity, validate, validate_sounds};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 成功；diff 时表示两边相同
+pub const EXIT_OK: i32 = 0;
+/// 校验有错误、读写失败；diff 时表示有差异
+pub const EXIT_FAILED: i32 = 1;
+/// 参数错误
+pub const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "用法:
+  validate <工作区>               校验数据、图标和音效引用
+  export-json <工作区> <目录>     把合并后的完整表导出为json
+  import-json <工作区> [目录]     把合并后的数据写成.bytes，默认写回 Config_Mod
+  pack <工作区> <模组目录>        生成可上传的模组目录
+  diff <工作区A> <工作区B>        比较两个工作区的数据";
+
+/// 命令行模式，第一个参数不是子命令时返回None，继续启动界面
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    let rest: Vec<&str> = rest.iter().map(String::as_str).collect();
+    let code = match (command.as_str(), rest.as_slice()) {
+        ("validate", [workspace]) => run_validate(Path::new(workspace)),
+        ("export-json", [workspace, out]) => run_export_json(Path::new(workspace), Path::new(out)),
+        ("import-json", [workspace]) => {
+            let out = data_dir(Path::new(workspace)).join("Config_Mod");
+            run_import_json(Path::new(workspace), &out)
+        }
+        ("import-json", [workspace, out]) => run_import_json(Path::new(workspace), Path::new(out)),
+        ("pack", [workspace, out]) => run_pack(Path::new(workspace), Path::new(out)),
+        ("diff", [a, b]) => run_diff(Path::new(a), Path::new(b)),
+        ("help" | "--help" | "-h", _) => {
+            println!("{}", USAGE);
+            EXIT_OK
+        }
+        ("validate" | "export-json" | "import-json" | "pack" | "diff", _) => {
+            eprintln!("{}", USAGE);
+            EXIT_USAGE
+        }
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// 读取工作区数据，读取过程中的任何错误都视为失败
+fn load_workspace(workspace: &Path) -> Result<TableData, Vec<String>> {
+    let path = data_dir(workspace);
+    if !path.exists() {
+        return Err(vec![format!("{}: 没有数据目录", path.display())]);
+    }
+    let mut messages = VecDeque::new();
+    let data = load_table_data(&path, &mut messages);
+    if messages.is_empty() {
+        Ok(data)
+    } else {
+        Err(messages.into())
+    }
+}
+
+fn print_errors(errors: &[String]) -> i32 {
+    for e in errors {
+        eprintln!("错误: {}", e);
+    }
+    EXIT_FAILED
+}
+
+fn print_issue(issue: &Issue) {
+    println!(
+        "{} {} #{}: {}",
+        issue.severity.name(),
+        issue.page.get_info().title,
+        issue.row_id,
+        issue.message
+    );
+}
+
+/// 数据、图标和音效的检查结果，validate 和 pack 共用
+struct Report {
+    issues: Vec<Issue>,
+    sound_errors: Vec<String>,
+}
+
+impl Report {
+    fn errors(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|x| x.severity == Severity::Error)
+            .count()
+            + self.sound_errors.len()
+    }
+
+    fn print(&self) {
+        for issue in &self.issues {
+            print_issue(issue);
+        }
+        for e in &self.sound_errors {
+            println!("{} 音效: {}", Severity::Error.name(), e);
+        }
+    }
+}
+
+/// 音效目录无法读取时返回错误
+fn check_workspace(workspace: &Path, data: &TableData) -> Result<Report, String> {
+    let mut issues = validate(data);
+
+    let mut sounds = SoundRegistry::default();
+    if let Some(e) = sounds.sync(workspace.to_str()) {
+        return Err(e);
+    }
+    if sounds.dir().is_some_and(Path::exists) {
+        issues.extend(validate_sounds(data, &sounds));
+    }
+    let mut images = ImageIndex::default();
+    images.sync(workspace.to_str());
+    if images.dir().is_some_and(Path::exists) {
+        for r in icon_references(data) {
+            if images.is_missing(&r.icon) {
+                issues.push(Issue {
+                    severity: Severity::Warning,
+                    page: r.page,
+                    index: r.index,
+                    row_id: r.row_id,
+                    message: format!("{} 的图片 {} 不在 Image_Mod 中", r.field, r.icon),
+                });
+            }
+        }
+    }
+    issues.sort_by_key(|x| x.severity);
+    Ok(Report {
+        issues,
+        sound_errors: sounds.validate(),
+    })
+}
+
+fn run_validate(workspace: &Path) -> i32 {
+    let data = match load_workspace(workspace) {
+        Ok(data) => data,
+        Err(errors) => return print_errors(&errors),
+    };
+    let report = match check_workspace(workspace, &data) {
+        Ok(report) => report,
+        Err(e) => return print_errors(&[e]),
+    };
+    report.print();
+    let errors = report.errors();
+    println!(
+        "错误 {}，警告 {}",
+        errors,
+        report.issues.len() + report.sound_errors.len() - errors
+    );
+    if errors == 0 { EXIT_OK } else { EXIT_FAILED }
+}
+
+fn run_export_json(workspace: &Path, out: &Path) -> i32 {
+    let data = match load_workspace(workspace) {
+        Ok(data) => data,
+        Err(errors) => return print_errors(&errors),
+    };
+    if let Err(e) = fs::create_dir_all(out) {
+        return print_errors(&[format!("{}: {}", out.display(), e)]);
+    }
+    let mut messages = VecDeque::new();
+    for page in SIDEBAR_PAGES {
+        let file = out.join(json_file_name(page));
+        if write_table_json(file.clone(), page, &data, &mut messages) {
+            println!("{}", file.display());
+        }
+    }
+    if messages.is_empty() {
+        EXIT_OK
+    } else {
+        print_errors(&Vec::from(messages))
+    }
+}
+
+fn run_import_json(workspace: &Path, out: &Path) -> i32 {
+    let data = match load_workspace(workspace) {
+        Ok(data) => data,
+        Err(errors) => return print_errors(&errors),
+    };
+    match write_luban_bytes(out, &data) {
+        Ok(()) => {
+            println!("已写入 {}", out.display());
+            EXIT_OK
+        }
+        Err(e) => print_errors(&[e.to_string()]),
+    }
+}
+
+/// 生成上传用的模组目录：Config_Mod 写入合并后的配置表，moddata.json、图片和音效原样复制
+fn run_pack(workspace: &Path, out: &Path) -> i32 {
+    let data = match load_workspace(workspace) {
+        Ok(data) => data,
+        Err(errors) => return print_errors(&errors),
+    };
+    let mod_data = workspace.join(MOD_DATA_FILE);
+    if !mod_data.exists() {
+        return print_errors(&[format!("{}: 文件不存在", mod_data.display())]);
+    }
+    // 和 validate 一样检查数据、图标和音效
+    let report = match check_workspace(workspace, &data) {
+        Ok(report) => report,
+        Err(e) => return print_errors(&[e]),
+    };
+    if report.errors() > 0 {
+        report.print();
+        return print_errors(&["校验未通过，没有打包".to_string()]);
+    }
+
+    if let Err(e) = write_luban_bytes(&out.join("Config_Mod"), &data) {
+        return print_errors(&[e.to_string()]);
+    }
+    if let Err(e) = fs::copy(&mod_data, out.join(MOD_DATA_FILE)) {
+        return print_errors(&[format!("{}: {}", mod_data.display(), e)]);
+    }
+    for dir in ["Image_Mod", "Sound_Mod"] {
+        let source = workspace.join(dir);
+        if source.exists()
+            && let Err(e) = copy_dir(&source, &out.join(dir))
+        {
+            return print_errors(&[format!("{}: {}", source.display(), e)]);
+        }
+    }
+    // 打包结果要能通过上传前检查
+    let preflight = check_mod_folder(out);
+    for finding in &preflight.findings {
+        println!(
+            "{} {}: {}",
+            finding.severity.name(),
+            finding.category.name(),
+            finding.message
+        );
+    }
+    if preflight.has_errors() {
+        return print_errors(&[format!("{} 没有通过上传前检查", out.display())]);
+    }
+    println!("已打包到 {}", out.display());
+    EXIT_OK
+}
+
+fn copy_dir(source: &Path, target: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let path: PathBuf = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &target.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, target.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+fn run_diff(a: &Path, b: &Path) -> i32 {
+    let (old, new) = match (load_workspace(a), load_workspace(b)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (old, new) => {
+            let errors: Vec<String> = [old.err(), new.err()]
+                .into_iter()
+                .flatten()
+                .flatten()
+                .collect();
+            print_errors(&errors);
+            return EXIT_USAGE;
+        }
+    };
+    let edits = diff_tables(&old, &new);
+    for edit in &edits {
+        let (page, _): (Page, usize) = edit.location();
+        println!("{}: {}", page.get_info().title, edit.describe());
+    }
+    if edits.is_empty() {
+        EXIT_OK
+    } else {
+        EXIT_FAILED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bean::Localization;
+    use crate::testutil::TempDir;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|x| x.to_string()).collect()
+    }
+
+    /// 两个数据相同的工作区a和b
+    fn workspaces() -> (TempDir, PathBuf, PathBuf) {
+        let root = TempDir::new("cli");
+        let (a, b) = (root.join("a"), root.join("b"));
+        let data = TableData {
+            l10n: vec![Localization {
+                id: 1,
+                cn: "一".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        write_luban_bytes(&data_dir(&a).join("Config_Mod"), &data).unwrap();
+        write_luban_bytes(&data_dir(&b).join("Config_Mod"), &data).unwrap();
+        (root, a, b)
+    }
+
+    fn path(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    #[test]
+    fn ignores_flags_and_rejects_missing_arguments() {
+        assert_eq!(run(&args(&["--some-flag"])), None);
+        assert_eq!(run(&args(&["diff", "x"])), Some(EXIT_USAGE));
+    }
+
+    #[test]
+    fn validate_and_diff_pass_for_identical_workspaces() {
+        let (_root, a, b) = workspaces();
+        assert_eq!(run(&args(&["validate", path(&a)])), Some(EXIT_OK));
+        assert_eq!(run(&args(&["diff", path(&a), path(&b)])), Some(EXIT_OK));
+    }
+
+    #[test]
+    fn validate_fails_without_data_dir() {
+        let (root, _, _) = workspaces();
+        assert_eq!(
+            run(&args(&["validate", path(root.path())])),
+            Some(EXIT_FAILED)
+        );
+    }
+
+    #[test]
+    fn import_json_writes_json_changes_into_bytes() {
+        let (_root, a, b) = workspaces();
+        fs::write(
+            data_dir(&b).join("localization_mod.json"),
+            r#"[{"Id": 2, "Cn": "二", "En": "", "Jp": "", "Cnt": ""}]"#,
+        )
+        .unwrap();
+        assert_eq!(run(&args(&["diff", path(&a), path(&b)])), Some(EXIT_FAILED));
+        assert_eq!(run(&args(&["import-json", path(&b)])), Some(EXIT_OK));
+        fs::remove_file(data_dir(&b).join("localization_mod.json")).unwrap();
+        // json是完整的表，只剩下json中的行
+        let l10n = load_workspace(&b).unwrap().l10n;
+        assert_eq!(l10n.iter().map(|x| x.id).collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn pack_requires_mod_data() {
+        let (root, a, _) = workspaces();
+        let out = root.join("out");
+        assert_eq!(
+            run(&args(&["pack", path(&a), path(&out)])),
+            Some(EXIT_FAILED)
+        );
+        assert!(!out.exists());
+    }
+
+    #[test]
+    fn packed_folder_passes_preflight() {
+        let (root, a, _) = workspaces();
+        fs::write(
+            a.join(MOD_DATA_FILE),
+            r#"{"ModName": "猫猫", "ModDesc": "描述", "Version": "1.0.0"}"#,
+        )
+        .unwrap();
+        let out = root.join("out");
+        assert_eq!(run(&args(&["pack", path(&a), path(&out)])), Some(EXIT_OK));
+        let report = check_mod_folder(&out);
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+    }
+
+    #[test]
+    fn pack_refuses_sound_errors() {
+        let (root, a, _) = workspaces();
+        fs::write(
+            a.join(MOD_DATA_FILE),
+            r#"{"ModName": "猫猫", "ModDesc": "描述", "Version": "1.0.0"}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(a.join("Sound_Mod")).unwrap();
+        fs::write(a.join("Sound_Mod").join("sound_mod.json"), "[{").unwrap();
+        let out = root.join("out");
+        assert_eq!(
+            run(&args(&["pack", path(&a), path(&out)])),
+            Some(EXIT_FAILED)
+        );
+    }
+
+    #[test]
+    fn export_json_writes_every_table() {
+        let (root, _, b) = workspaces();
+        let out = root.join("out");
+        assert_eq!(
+            run(&args(&["export-json", path(&b), path(&out)])),
+            Some(EXIT_OK)
+        );
+        assert!(out.join("localization_mod.json").exists());
+    }
+}
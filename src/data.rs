@@ -1,11 +1,19 @@
 use crate::MyApp;
+use crate::backup;
 use crate::bean::*;
+use crate::page::Page;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use crate::luban::load_tables;
+use std::sync::Arc;
+use crate::luban::{load_tables, save_tables};
+use cfg::prelude::{
+    LubanError, Tables, tbelement, tbenemy, tblocalization, tbraceattribute, tbrelics,
+};
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct TableData {
     pub relics: Vec<Relics>,
     pub elements: Vec<Element>,
@@ -14,85 +22,179 @@ pub struct TableData {
     pub race_attributes: Vec<RaceAttribute>,
 }
 
+impl TableData {
+    /// 与已保存的数据相比有修改的表
+    pub fn dirty_tables(&self, saved: &TableData) -> Vec<Page> {
+        let mut dirty = Vec::new();
+        if !same_rows(&self.relics, &saved.relics) {
+            dirty.push(Page::Relic);
+        }
+        if !same_rows(&self.elements, &saved.elements) {
+            dirty.push(Page::Element);
+        }
+        if !same_rows(&self.enemies, &saved.enemies) {
+            dirty.push(Page::Enemy);
+        }
+        if !same_rows(&self.l10n, &saved.l10n) {
+            dirty.push(Page::L10n);
+        }
+        if !same_rows(&self.race_attributes, &saved.race_attributes) {
+            dirty.push(Page::RaceAttribute);
+        }
+        dirty
+    }
+
+    /// 用另一份数据中的同一张表替换
+    pub fn copy_table(&mut self, from: &TableData, page: &Page) {
+        match page {
+            Page::Relic => self.relics = from.relics.clone(),
+            Page::Element => self.elements = from.elements.clone(),
+            Page::Enemy => self.enemies = from.enemies.clone(),
+            Page::L10n => self.l10n = from.l10n.clone(),
+            Page::RaceAttribute => self.race_attributes = from.race_attributes.clone(),
+        }
+    }
+}
+
+/// 表对应的json文件名
+pub fn json_file_name(page: &Page) -> &'static str {
+    match page {
+        Page::Relic => "relics_mod.json",
+        Page::Element => "element_mod.json",
+        Page::Enemy => "enemy_mod.json",
+        Page::L10n => "localization_mod.json",
+        Page::RaceAttribute => "raceattribute_mod.json",
+    }
+}
+
+/// 工作区中的数据目录
+pub fn data_dir(workspace: &Path) -> PathBuf {
+    workspace.join("Config").join("Config").join("Datas")
+}
+
+/// 编辑器为工作区保存的备份等文件，放在工作区旁边，不会被当作模组内容上传
+pub fn editor_dir(workspace: &Path) -> PathBuf {
+    let mut name = workspace.file_name().unwrap_or_default().to_os_string();
+    name.push(".editor");
+    workspace.with_file_name(name)
+}
+
 fn get_data_path(app: &mut MyApp) -> Option<PathBuf> {
     app.get_current_workspace().and_then(|workspace| {
-        let path = Path::new(workspace)
-            .join("Config")
-            .join("Config")
-            .join("Datas");
+        let path = data_dir(Path::new(workspace));
         if path.exists() { Some(path) } else { None }
     })
 }
 
-/// 将json数据读取到编辑器没
+/// 将配置表数据读取到编辑器，有json的表使用json中的数据
 pub fn read_data(app: &mut MyApp) {
     if let Some(path) = get_data_path(app) {
-        read_luban_bytes(path);
-        /*
-        read_json(
-            path.join("element_mod.json"),
-            &mut app.app_state.table_data.elements,
-            &mut app.app_state.messages,
-        );
-        read_json(
-            path.join("localization_mod.json"),
-            &mut app.app_state.table_data.l10n,
-            &mut app.app_state.messages,
-        );
-        read_json(
-            path.join("raceattribute_mod.json"),
-            &mut app.app_state.table_data.race_attributes,
-            &mut app.app_state.messages,
-        );
-        read_json(
-            path.join("enemy_mod.json"),
-            &mut app.app_state.table_data.enemies,
-            &mut app.app_state.messages,
-        );
-        read_json(
-            path.join("relics_mod.json"),
-            &mut app.app_state.table_data.relics,
-            &mut app.app_state.messages,
-        );
-        
-         */
-    }
-}
-
-/// 将编辑器数据保存为json
+        app.app_state.table_data = load_table_data(&path, &mut app.app_state.messages);
+    } else {
+        app.app_state
+            .messages
+            .push_back("当前模组没有数据目录".to_string());
+    }
+    app.app_state.history.reset(&app.app_state.table_data);
+    app.app_state.saved_data = app.app_state.table_data.clone();
+}
+
+/// 读取数据目录中的配置表，有json的表使用json中的数据
+pub fn load_table_data(path: &Path, messages: &mut VecDeque<String>) -> TableData {
+    let mut table_data = read_luban_bytes(path, messages).unwrap_or_default();
+    read_json_tables(path, &mut table_data, messages);
+    table_data
+}
+
+/// 用目录中的 *_mod.json 替换对应的整张表，json中删除的行不会再出现
+pub fn read_json_tables(path: &Path, table_data: &mut TableData, messages: &mut VecDeque<String>) {
+    read_json(
+        path.join("element_mod.json"),
+        &mut table_data.elements,
+        messages,
+    );
+    read_json(
+        path.join("localization_mod.json"),
+        &mut table_data.l10n,
+        messages,
+    );
+    read_json(
+        path.join("raceattribute_mod.json"),
+        &mut table_data.race_attributes,
+        messages,
+    );
+    read_json(
+        path.join("enemy_mod.json"),
+        &mut table_data.enemies,
+        messages,
+    );
+    read_json(
+        path.join("relics_mod.json"),
+        &mut table_data.relics,
+        messages,
+    );
+}
+
+/// 将有修改的表保存为json，覆盖前先备份旧文件
 pub fn save_data(app: &mut MyApp) {
-    if let Some(path) = get_data_path(app) {
-        write_json(
-            path.join("element_mod.json"),
-            &mut app.app_state.table_data.elements,
-            &mut app.app_state.messages,
-        );
-        write_json(
-            path.join("localization_mod.json"),
-            &mut app.app_state.table_data.l10n,
-            &mut app.app_state.messages,
-        );
-        write_json(
-            path.join("raceattribute_mod.json"),
-            &mut app.app_state.table_data.race_attributes,
-            &mut app.app_state.messages,
-        );
-        write_json(
-            path.join("enemy_mod.json"),
-            &mut app.app_state.table_data.enemies,
-            &mut app.app_state.messages,
-        );
-        write_json(
-            path.join("relics_mod.json"),
-            &mut app.app_state.table_data.relics,
-            &mut app.app_state.messages,
-        );
+    let Some(path) = get_data_path(app) else {
+        return;
+    };
+    let workspace = PathBuf::from(app.get_current_workspace().unwrap_or_default());
+    let state = &mut app.app_state;
+    let data = &state.table_data;
+    let saved = &mut state.saved_data;
+    let messages = &mut state.messages;
+    for page in data.dirty_tables(saved) {
+        let file = path.join(json_file_name(&page));
+        if file.exists()
+            && let Err(e) = backup::backup_table(&workspace, &page, &file)
+        {
+            messages.push_back(format!("备份{}失败: {}", file.display(), e));
+        }
+        if write_table_json(file, &page, data, messages) {
+            saved.copy_table(data, &page);
+        }
+    }
+}
+
+/// 把一张表完整写成json，失败时返回false
+pub fn write_table_json(
+    path: PathBuf,
+    page: &Page,
+    data: &TableData,
+    messages: &mut VecDeque<String>,
+) -> bool {
+    match page {
+        Page::Relic => write_json(path, &data.relics, messages),
+        Page::Element => write_json(path, &data.elements, messages),
+        Page::Enemy => write_json(path, &data.enemies, messages),
+        Page::L10n => write_json(path, &data.l10n, messages),
+        Page::RaceAttribute => write_json(path, &data.race_attributes, messages),
+    }
+}
+
+/// 用备份替换当前数据中的一张表，备份和json一样是完整的表
+pub fn read_backup(app: &mut MyApp, page: &Page, backup: &Path) -> Option<TableData> {
+    get_data_path(app)?;
+    let messages = &mut app.app_state.messages;
+    let mut data = app.app_state.table_data.clone();
+    let backup = backup.to_path_buf();
+    match page {
+        Page::Relic => read_json(backup, &mut data.relics, messages),
+        Page::Element => read_json(backup, &mut data.elements, messages),
+        Page::Enemy => read_json(backup, &mut data.enemies, messages),
+        Page::L10n => read_json(backup, &mut data.l10n, messages),
+        Page::RaceAttribute => read_json(backup, &mut data.race_attributes, messages),
     }
+    Some(data)
 }
 
 /// 清除编辑器内数据
 pub fn clear_data(app: &mut MyApp) {
     app.app_state.table_data = Default::default();
+    app.app_state.history.reset(&app.app_state.table_data);
+    app.app_state.saved_data = Default::default();
 }
 
 fn read_json<T>(path: PathBuf, vec: &mut Vec<T>, messages: &mut VecDeque<String>)
@@ -100,41 +202,219 @@ where
     T: for<'de> Deserialize<'de>,
 {
     if path.exists() {
-        match std::fs::read_to_string(path) {
+        match std::fs::read_to_string(&path) {
             Ok(json) => match serde_json::from_str::<Vec<T>>(&json) {
                 Ok(data) => *vec = data,
-                Err(e) => messages.push_back(format!("{}", e)),
+                Err(e) => messages.push_back(format!("{}: {}", path.display(), e)),
             },
-            Err(e) => messages.push_back(format!("{}", e)),
+            Err(e) => messages.push_back(format!("{}: {}", path.display(), e)),
         }
     }
-    else {
-
-    }
 }
 
-fn write_json<T>(path: PathBuf, vec: &mut Vec<T>, messages: &mut VecDeque<String>)
+/// 写入json，失败时返回false
+fn write_json<T>(path: PathBuf, vec: &[T], messages: &mut VecDeque<String>) -> bool
 where
     T: for<'de> Deserialize<'de> + Serialize,
 {
-    let mut file = match std::fs::File::create(path) {
-        Ok(file) => file,
+    let json = match serde_json::to_string_pretty(vec) {
+        Ok(json) => json,
         Err(e) => {
-            messages.push_back(format!("{}", e));
-            return;
+            messages.push_back(format!("{}: {}", path.display(), e));
+            return false;
         }
     };
-    if let Err(e) = serde_json::to_writer_pretty(&mut file, vec) {
-        messages.push_back(format!("{}", e));
+    if let Err(e) = write_atomic(&path, json.as_bytes()) {
+        messages.push_back(format!("{}: {}", path.display(), e));
+        return false;
     }
+    true
 }
 
-fn read_luban_bytes(path: PathBuf) {
+/// 先写入临时文件再重命名，写入中途失败不会破坏原文件
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp = path.with_file_name(temp_name);
+    let result = fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+fn read_luban_bytes(path: &Path, messages: &mut VecDeque<String>) -> Option<TableData> {
     let table_path = path.join("Config_Mod");
-    let tables = load_tables(table_path.to_str().unwrap());
-    let _relics = tables.tbrelics;
-    let _element = tables.tbelement;
-    let _enemy = tables.tbenemy;
-    let _race  = tables.tbraceattribute;
-    let _l10n = tables.tblocalization;
-}
\ No newline at end of file
+    let tables = match load_tables(table_path.to_str().unwrap()) {
+        Ok(tables) => tables,
+        Err(e) => {
+            messages.push_back(format!("加载配置表失败: {}", e));
+            return None;
+        }
+    };
+    Some(table_data_from(&tables))
+}
+
+/// 把配置表转换成编辑器数据
+pub fn table_data_from(tables: &Tables) -> TableData {
+    TableData {
+        relics: convert_rows(&tables.tbrelics.data_list),
+        elements: convert_rows(&tables.tbelement.data_list),
+        enemies: convert_rows(&tables.tbenemy.data_list),
+        l10n: convert_rows(&tables.tblocalization.data_list),
+        race_attributes: convert_rows(&tables.tbraceattribute.data_list),
+    }
+}
+
+fn convert_rows<S, T>(rows: &[Arc<S>]) -> Vec<T>
+where
+    T: for<'a> From<&'a S>,
+{
+    rows.iter().map(|x| T::from(x.as_ref())).collect()
+}
+
+/// 把编辑器数据写成配置表的.bytes文件
+pub fn write_luban_bytes(path: &Path, data: &TableData) -> Result<(), LubanError> {
+    save_tables(path, &build_tables(data))
+}
+
+/// 把编辑器数据转换成配置表
+pub fn build_tables(data: &TableData) -> Tables {
+    Tables {
+        tbelement: Arc::new(tbelement {
+            data_map: rows_by_id(&data.elements),
+            data_list: arc_rows(&data.elements),
+        }),
+        tblocalization: Arc::new(tblocalization {
+            data_map: rows_by_id(&data.l10n),
+            data_list: arc_rows(&data.l10n),
+        }),
+        tbraceattribute: Arc::new(tbraceattribute {
+            data_map: rows_by_id(&data.race_attributes),
+            data_list: arc_rows(&data.race_attributes),
+        }),
+        tbenemy: Arc::new(tbenemy {
+            data_map: rows_by_id(&data.enemies),
+            data_list: arc_rows(&data.enemies),
+        }),
+        tbrelics: Arc::new(tbrelics {
+            data_map: rows_by_id(&data.relics),
+            data_list: arc_rows(&data.relics),
+        }),
+    }
+}
+
+fn arc_rows<S, T>(rows: &[S]) -> Vec<Arc<T>>
+where
+    T: for<'a> From<&'a S>,
+{
+    rows.iter().map(|x| Arc::new(T::from(x))).collect()
+}
+
+fn rows_by_id<S, T>(rows: &[S]) -> HashMap<i32, Arc<T>>
+where
+    S: TableRow,
+    T: for<'a> From<&'a S>,
+{
+    rows.iter()
+        .map(|x| (x.id(), Arc::new(T::from(x))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    fn l10n(id: i32, cn: &str) -> Localization {
+        Localization {
+            id,
+            cn: cn.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 配置表中有1、2、3三行的数据目录
+    fn data_dir() -> TempDir {
+        let dir = TempDir::new("data");
+        let data = TableData {
+            l10n: vec![l10n(1, "一"), l10n(2, "二"), l10n(3, "三")],
+            ..Default::default()
+        };
+        write_luban_bytes(&dir.join("Config_Mod"), &data).unwrap();
+        dir
+    }
+
+    fn save_and_reload(dir: &TempDir, data: &TableData) -> TableData {
+        let mut messages = VecDeque::new();
+        let file = dir.join(json_file_name(&Page::L10n));
+        assert!(write_table_json(file, &Page::L10n, data, &mut messages));
+        let loaded = load_table_data(dir.path(), &mut messages);
+        assert!(messages.is_empty(), "{:?}", messages);
+        loaded
+    }
+
+    #[test]
+    fn deleted_rows_stay_deleted_after_reload() {
+        let dir = data_dir();
+        let mut data = load_table_data(dir.path(), &mut VecDeque::new());
+        data.l10n.remove(1);
+        let loaded = save_and_reload(&dir, &data);
+        assert_eq!(loaded.l10n, vec![l10n(1, "一"), l10n(3, "三")]);
+    }
+
+    #[test]
+    fn changed_ids_are_not_duplicated_after_reload() {
+        let dir = data_dir();
+        let mut data = load_table_data(dir.path(), &mut VecDeque::new());
+        data.l10n[0].id = 4;
+        let loaded = save_and_reload(&dir, &data);
+        assert_eq!(loaded.l10n, data.l10n);
+    }
+
+    #[test]
+    fn tables_without_json_come_from_bytes() {
+        let dir = data_dir();
+        let loaded = load_table_data(dir.path(), &mut VecDeque::new());
+        assert_eq!(loaded.l10n.len(), 3);
+    }
+
+    #[test]
+    fn dirty_tables_compare_with_saved_data() {
+        let saved = TableData {
+            l10n: vec![l10n(1, "一")],
+            ..Default::default()
+        };
+        let mut data = saved.clone();
+        assert!(data.dirty_tables(&saved).is_empty());
+
+        data.l10n[0].en = "One".to_string();
+        data.enemies.push(Enemy::default());
+        assert_eq!(data.dirty_tables(&saved), vec![Page::Enemy, Page::L10n]);
+
+        data.l10n[0].en.clear();
+        assert_eq!(data.dirty_tables(&saved), vec![Page::Enemy]);
+    }
+
+    #[test]
+    fn write_atomic_replaces_file_without_leftovers() {
+        let dir = TempDir::new("atomic");
+        let path = dir.join("relics_mod.json");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // 目标是目录时重命名失败，临时文件也要清理
+        let blocked = dir.join("blocked");
+        fs::create_dir_all(blocked.join("child")).unwrap();
+        assert!(write_atomic(&blocked, b"x").is_err());
+        assert!(!dir.join("blocked.tmp").exists());
+    }
+}
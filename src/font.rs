@@ -1,15 +1,58 @@
 use super::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-// 设置自定义字体以支持中文显示
-pub fn setup_custom_fonts(ctx: &egui::Context) {
+/// 常见的中文字体文件名，按优先级排列
+const CJK_FONT_FILES: &[&str] = &[
+    // Windows
+    "msyh.ttc",
+    "msyh.ttf",
+    "simsun.ttc",
+    "simhei.ttf",
+    // macOS
+    "PingFang.ttc",
+    "Hiragino Sans GB.ttc",
+    "STHeiti Medium.ttc",
+    "STHeiti Light.ttc",
+    "Songti.ttc",
+    "Arial Unicode.ttf",
+    // Linux
+    "NotoSansCJK-Regular.ttc",
+    "NotoSansCJKsc-Regular.otf",
+    "NotoSansSC-Regular.otf",
+    "NotoSansSC-Regular.ttf",
+    "SourceHanSansSC-Regular.otf",
+    "SourceHanSans-Regular.ttc",
+    "NotoSerifCJK-Regular.ttc",
+    "wqy-microhei.ttc",
+    "wqy-zenhei.ttc",
+    "DroidSansFallbackFull.ttf",
+    "uming.ttc",
+];
+
+/// 递归搜索字体目录的最大深度
+const MAX_SEARCH_DEPTH: usize = 4;
+
+// 设置自定义字体以支持中文显示，返回使用的字体文件或者警告
+pub fn setup_custom_fonts(ctx: &egui::Context, custom: Option<&str>) -> Result<PathBuf, String> {
     // 从默认字体开始（我们将添加而不是替换它们）
     let mut fonts = egui::FontDefinitions::default();
 
-    // 尝试加载系统微软雅黑字体文件
-    let font_data = load_system_font_data("msyh.ttc")
-        .or_else(|| load_system_font_data("msyh.ttf"))
-        .or_else(|| load_system_font_data("simsun.ttc"))
-        .unwrap();
+    let mut warnings = Vec::new();
+    let custom = custom.map(str::trim).filter(|x| !x.is_empty());
+    let custom = custom.and_then(|path| match load_font_file(Path::new(path)) {
+        Ok(data) => Some((PathBuf::from(path), data)),
+        Err(e) => {
+            warnings.push(e);
+            None
+        }
+    });
+    let Some((path, font_data)) = custom.or_else(find_system_font) else {
+        // 找不到中文字体时使用egui自带字体，中文会显示为方框
+        warnings.push("没有找到中文字体，请在设置中选择字体文件".to_string());
+        ctx.set_fonts(fonts);
+        return Err(warnings.join("；"));
+    };
 
     fonts.font_data.insert(
         "system_font".to_owned(),
@@ -19,28 +62,252 @@ pub fn setup_custom_fonts(ctx: &egui::Context) {
     // 将自定义字体添加到比例字体族中（用于用户界面）
     fonts
         .families
-        .get_mut(&egui::FontFamily::Proportional)
-        .unwrap()
+        .entry(egui::FontFamily::Proportional)
+        .or_default()
         .insert(0, "system_font".to_owned());
 
     // 将自定义字体添加到等宽字体族中（用于代码）
     fonts
         .families
-        .get_mut(&egui::FontFamily::Monospace)
-        .unwrap()
+        .entry(egui::FontFamily::Monospace)
+        .or_default()
         .insert(0, "system_font".to_owned());
 
     // 应用字体设置
     ctx.set_fonts(fonts);
+    if warnings.is_empty() {
+        Ok(path)
+    } else {
+        Err(format!(
+            "{}，已改用 {}",
+            warnings.join("；"),
+            path.display()
+        ))
+    }
 }
 
+/// 读取字体文件并检查能否解析，避免egui加载时崩溃
+pub fn load_font_file(path: &Path) -> Result<Vec<u8>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    ab_glyph::FontRef::try_from_slice(&data)
+        .map_err(|_| format!("{}: 不是有效的字体文件", path.display()))?;
+    Ok(data)
+}
 
 // 加载系统字体数据
-fn load_system_font_data(font_file: &str) -> Option<Vec<u8>> {
-    // Windows字体路径
-    let windows_font_path = format!("C:\\Windows\\Fonts\\{}", font_file);
-    if std::path::Path::new(&windows_font_path).exists() {
-        return std::fs::read(windows_font_path).ok();
-    }
-    None
-}
\ No newline at end of file
+fn find_system_font() -> Option<(PathBuf, Vec<u8>)> {
+    // 每个目录只遍历一次，再按优先级查找
+    let mut files = HashMap::new();
+    for dir in font_dirs() {
+        index_font_files(&dir, MAX_SEARCH_DEPTH, &mut files);
+    }
+    for font_file in CJK_FONT_FILES {
+        if let Some(path) = files.get(&font_file.to_lowercase())
+            && let Ok(data) = load_font_file(path)
+        {
+            return Some((path.clone(), data));
+        }
+    }
+    fontconfig_match().and_then(|path| Some((path.clone(), load_font_file(&path).ok()?)))
+}
+
+/// 各平台的字体目录
+fn font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if cfg!(windows) {
+        let windir = std::env::var("WINDIR").unwrap_or_else(|_| "C:\\Windows".to_string());
+        dirs.push(Path::new(&windir).join("Fonts"));
+        if let Ok(local) = std::env::var("LOCALAPPDATA") {
+            dirs.push(
+                Path::new(&local)
+                    .join("Microsoft")
+                    .join("Windows")
+                    .join("Fonts"),
+            );
+        }
+    } else if cfg!(target_os = "macos") {
+        dirs.push(PathBuf::from("/System/Library/Fonts"));
+        dirs.push(PathBuf::from("/Library/Fonts"));
+    } else {
+        dirs.push(PathBuf::from("/usr/share/fonts"));
+        dirs.push(PathBuf::from("/usr/local/share/fonts"));
+        if let Ok(data_home) = std::env::var("XDG_DATA_HOME") {
+            dirs.push(Path::new(&data_home).join("fonts"));
+        }
+    }
+    if let Ok(home) = std::env::var("HOME") {
+        let home = Path::new(&home);
+        dirs.push(home.join("Library").join("Fonts"));
+        dirs.push(home.join(".local").join("share").join("fonts"));
+        dirs.push(home.join(".fonts"));
+    }
+    dirs.retain(|x| x.is_dir());
+    dirs
+}
+
+/// 记录目录及子目录中的文件，键为小写的文件名，同名时保留先找到的
+fn index_font_files(dir: &Path, depth: usize, files: &mut HashMap<String, PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut subdirs = Vec::new();
+    for path in entries.flatten().map(|x| x.path()) {
+        if path.is_dir() {
+            subdirs.push(path);
+        } else if let Some(name) = path.file_name().and_then(|x| x.to_str()) {
+            files.entry(name.to_lowercase()).or_insert(path);
+        }
+    }
+    if depth == 0 {
+        return;
+    }
+    for subdir in subdirs {
+        index_font_files(&subdir, depth - 1, files);
+    }
+}
+
+/// 没有找到已知字体时询问fontconfig
+///
+/// `fc-match` 总会返回一个字体，即使它不支持中文，所以这里列出支持中文的字体
+fn fontconfig_match() -> Option<PathBuf> {
+    if cfg!(windows) {
+        return None;
+    }
+    let output = std::process::Command::new("fc-list")
+        .args([":lang=zh", "-f", "%{file}\n"])
+        .output()
+        .ok()?;
+    first_font_file(&String::from_utf8(output.stdout).ok()?)
+}
+
+/// `fc-list` 输出中第一个存在的字体文件
+fn first_font_file(output: &str) -> Option<PathBuf> {
+    output
+        .lines()
+        .map(|x| PathBuf::from(x.trim()))
+        .find(|x| !x.as_os_str().is_empty() && x.is_file())
+}
+
+#[derive(Default)]
+pub struct SettingsWindowState {
+    pub open: bool,
+    font_path: String,
+    /// 当前使用的字体或加载字体时的警告
+    pub font_status: Option<Result<PathBuf, String>>,
+}
+
+/// 打开设置窗口
+pub fn open_settings_window(app: &mut MyApp) {
+    let state = &mut app.app_state.settings_window;
+    state.font_path = app.app_data.font_path.clone().unwrap_or_default();
+    state.open = true;
+}
+
+pub fn render_settings_window(app: &mut MyApp, ctx: &egui::Context) {
+    let mut open = app.app_state.settings_window.open;
+    if !open {
+        return;
+    }
+    let mut apply = None;
+    egui::Window::new("设置")
+        .open(&mut open)
+        .default_width(480.0)
+        .show(ctx, |ui| {
+            let state = &mut app.app_state.settings_window;
+            ui.strong("字体");
+            match &state.font_status {
+                Some(Ok(path)) => {
+                    ui.label(format!("当前字体: {}", path.display()));
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(Color32::from_rgb(230, 160, 60), e);
+                }
+                None => {}
+            }
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut state.font_path)
+                        .hint_text("留空时自动查找系统中文字体")
+                        .desired_width(320.0),
+                );
+                if ui.button("浏览").clicked()
+                    && let Some(path) = rfd::FileDialog::new()
+                        .set_title("选择字体文件")
+                        .add_filter("字体", &["ttf", "ttc", "otf", "otc"])
+                        .pick_file()
+                {
+                    state.font_path = path.display().to_string();
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("应用").clicked() {
+                    apply = Some(state.font_path.trim().to_string());
+                }
+                if ui.button("自动查找").clicked() {
+                    state.font_path.clear();
+                    apply = Some(String::new());
+                }
+            });
+        });
+    app.app_state.settings_window.open = open;
+
+    if let Some(path) = apply {
+        let path = (!path.is_empty()).then_some(path);
+        let status = setup_custom_fonts(ctx, path.as_deref());
+        if let Err(e) = &status {
+            app.app_state.messages.push_back(e.clone());
+        }
+        // 只有能加载的字体才保存，避免下次启动时再报错
+        if path.is_none() || status.is_ok() {
+            app.app_data.font_path = path;
+        }
+        app.app_state.settings_window.font_status = Some(status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    /// 嵌套目录中有一个扩展名为大写的假字体文件
+    fn font_dir() -> (TempDir, std::path::PathBuf) {
+        let root = TempDir::new("font");
+        let nested = root.join("opentype").join("noto");
+        std::fs::create_dir_all(&nested).unwrap();
+        let file = nested.join("NotoSansCJK-Regular.TTC");
+        std::fs::write(&file, b"not a font").unwrap();
+        (root, file)
+    }
+
+    #[test]
+    fn indexes_font_files_case_insensitively() {
+        let (root, file) = font_dir();
+        let mut files = HashMap::new();
+        index_font_files(root.path(), MAX_SEARCH_DEPTH, &mut files);
+        assert_eq!(files.get("notosanscjk-regular.ttc"), Some(&file));
+
+        let mut shallow = HashMap::new();
+        index_font_files(root.path(), 1, &mut shallow);
+        assert!(shallow.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_and_missing_font_files() {
+        let (root, file) = font_dir();
+        assert!(load_font_file(&file).is_err());
+        assert!(load_font_file(&root.join("missing.ttf")).is_err());
+    }
+
+    #[test]
+    fn takes_first_existing_fc_list_file() {
+        let (root, file) = font_dir();
+        let output = format!(
+            "{}\n\n{}\n",
+            root.join("missing.ttf").display(),
+            file.display()
+        );
+        assert_eq!(first_font_file(&output), Some(file));
+        assert_eq!(first_font_file(""), None);
+    }
+}
@@ -11,7 +11,7 @@
 use super::*;
 use luban_lib::*;
 
-#[derive(Debug, Hash, Eq, PartialEq, macros::EnumFromNum)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, macros::EnumFromNum)]
 pub enum ERaceType {
     None = 0,
     Item = 1,
@@ -37,37 +37,39 @@ pub enum ERaceType {
     Mod8 = 21,
 }
 
-impl From<i32> for ERaceType {
-    fn from(value: i32) -> Self {
+impl TryFrom<i32> for ERaceType {
+    type Error = LubanError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
         match value {
-            0 => ERaceType::None,
-            1 => ERaceType::Item,
-            2 => ERaceType::Cat,
-            3 => ERaceType::Dog,
-            4 => ERaceType::Bird,
-            5 => ERaceType::Bug,
-            6 => ERaceType::Fish,
-            7 => ERaceType::Dragon,
-            8 => ERaceType::Snake,
-            9 => ERaceType::Hoofed,
-            10 => ERaceType::Lizard,
-            11 => ERaceType::Rodents,
-            12 => ERaceType::Molluscs,
-            13 => ERaceType::Furry,
-            14 => ERaceType::Mod1,
-            15 => ERaceType::Mod2,
-            16 => ERaceType::Mod3,
-            17 => ERaceType::Mod4,
-            18 => ERaceType::Mod5,
-            19 => ERaceType::Mod6,
-            20 => ERaceType::Mod7,
-            21 => ERaceType::Mod8,
-            _ => panic!("Invalid value for ERaceType:{}", value),
+            0 => Ok(ERaceType::None),
+            1 => Ok(ERaceType::Item),
+            2 => Ok(ERaceType::Cat),
+            3 => Ok(ERaceType::Dog),
+            4 => Ok(ERaceType::Bird),
+            5 => Ok(ERaceType::Bug),
+            6 => Ok(ERaceType::Fish),
+            7 => Ok(ERaceType::Dragon),
+            8 => Ok(ERaceType::Snake),
+            9 => Ok(ERaceType::Hoofed),
+            10 => Ok(ERaceType::Lizard),
+            11 => Ok(ERaceType::Rodents),
+            12 => Ok(ERaceType::Molluscs),
+            13 => Ok(ERaceType::Furry),
+            14 => Ok(ERaceType::Mod1),
+            15 => Ok(ERaceType::Mod2),
+            16 => Ok(ERaceType::Mod3),
+            17 => Ok(ERaceType::Mod4),
+            18 => Ok(ERaceType::Mod5),
+            19 => Ok(ERaceType::Mod6),
+            20 => Ok(ERaceType::Mod7),
+            21 => Ok(ERaceType::Mod8),
+            _ => Err(LubanError::Bean(format!("Invalid value for ERaceType:{}", value))),
         }
     }
 }
 
-#[derive(Debug, Hash, Eq, PartialEq, macros::EnumFromNum)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, macros::EnumFromNum)]
 pub enum ERare {
     None = 0,
     Common = 1,
@@ -77,21 +79,23 @@ pub enum ERare {
     Mythic = 5,
 }
 
-impl From<i32> for ERare {
-    fn from(value: i32) -> Self {
+impl TryFrom<i32> for ERare {
+    type Error = LubanError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
         match value {
-            0 => ERare::None,
-            1 => ERare::Common,
-            2 => ERare::Rare,
-            3 => ERare::Legend,
-            4 => ERare::Epic,
-            5 => ERare::Mythic,
-            _ => panic!("Invalid value for ERare:{}", value),
+            0 => Ok(ERare::None),
+            1 => Ok(ERare::Common),
+            2 => Ok(ERare::Rare),
+            3 => Ok(ERare::Legend),
+            4 => Ok(ERare::Epic),
+            5 => Ok(ERare::Mythic),
+            _ => Err(LubanError::Bean(format!("Invalid value for ERare:{}", value))),
         }
     }
 }
 
-#[derive(Debug, Hash, Eq, PartialEq, macros::EnumFromNum)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, macros::EnumFromNum)]
 pub enum Etip {
     None = 0,
     Sum = 1,
@@ -101,16 +105,18 @@ pub enum Etip {
     Near = 5,
 }
 
-impl From<i32> for Etip {
-    fn from(value: i32) -> Self {
+impl TryFrom<i32> for Etip {
+    type Error = LubanError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
         match value {
-            0 => Etip::None,
-            1 => Etip::Sum,
-            2 => Etip::Before,
-            3 => Etip::Prob,
-            4 => Etip::SumEnd,
-            5 => Etip::Near,
-            _ => panic!("Invalid value for Etip:{}", value),
+            0 => Ok(Etip::None),
+            1 => Ok(Etip::Sum),
+            2 => Ok(Etip::Before),
+            3 => Ok(Etip::Prob),
+            4 => Ok(Etip::SumEnd),
+            5 => Ok(Etip::Near),
+            _ => Err(LubanError::Bean(format!("Invalid value for Etip:{}", value))),
         }
     }
 }
@@ -163,32 +169,57 @@ pub struct Element {
 
 impl Element{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<Element, LubanError> {
-        let Id = buf.read_int();
-        let Lock = buf.read_int();
-        let Enable = buf.read_int();
-        let Role = buf.read_int();
-        let name_id = buf.read_int();
-        let desc_id = buf.read_int();
-        let desctip = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.read_int().into()); } _e0 };
-        let DescAttribute = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.read_int()); } _e0 };
-        let Icon = buf.read_string();
-        let Rare = buf.read_int();
-        let RaceType = buf.read_int().into();
-        let OtherRace = buf.read_int().into();
-        let Attribute = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(crate::ElementAttribute::new(&mut buf)?); } _e0 };
-        let TriggerType = buf.read_int();
-        let TriggerParam = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.read_int()); } _e0 };
-        let EventTip = buf.read_int();
-        let TriggerAction = buf.read_int();
-        let TriggerValue = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(crate::ElementTriggerValue::new(&mut buf)?); } _e0 };
-        let OtherValue = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.read_int()); } _e0 };
-        let AttackSound = buf.read_int();
-        let SelectSound = buf.read_int();
-        let AttackParticle = buf.read_int();
+        let Id = buf.try_read_int()?;
+        let Lock = buf.try_read_int()?;
+        let Enable = buf.try_read_int()?;
+        let Role = buf.try_read_int()?;
+        let name_id = buf.try_read_int()?;
+        let desc_id = buf.try_read_int()?;
+        let desctip = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.try_read_int()?.try_into()?); } _e0 };
+        let DescAttribute = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.try_read_int()?); } _e0 };
+        let Icon = buf.try_read_string()?;
+        let Rare = buf.try_read_int()?;
+        let RaceType = buf.try_read_int()?.try_into()?;
+        let OtherRace = buf.try_read_int()?.try_into()?;
+        let Attribute = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(crate::ElementAttribute::new(&mut buf)?); } _e0 };
+        let TriggerType = buf.try_read_int()?;
+        let TriggerParam = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.try_read_int()?); } _e0 };
+        let EventTip = buf.try_read_int()?;
+        let TriggerAction = buf.try_read_int()?;
+        let TriggerValue = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(crate::ElementTriggerValue::new(&mut buf)?); } _e0 };
+        let OtherValue = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.try_read_int()?); } _e0 };
+        let AttackSound = buf.try_read_int()?;
+        let SelectSound = buf.try_read_int()?;
+        let AttackParticle = buf.try_read_int()?;
         
         Ok(Element { Id, Lock, Enable, Role, name_id, desc_id, desctip, DescAttribute, Icon, Rare, RaceType, OtherRace, Attribute, TriggerType, TriggerParam, EventTip, TriggerAction, TriggerValue, OtherValue, AttackSound, SelectSound, AttackParticle, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_int(self.Id);
+        buf.write_int(self.Lock);
+        buf.write_int(self.Enable);
+        buf.write_int(self.Role);
+        buf.write_int(self.name_id);
+        buf.write_int(self.desc_id);
+        buf.write_size(self.desctip.len()); for _e0 in &self.desctip { buf.write_int(*_e0 as i32); }
+        buf.write_size(self.DescAttribute.len()); for _e0 in &self.DescAttribute { buf.write_int(*_e0); }
+        buf.write_string(&self.Icon);
+        buf.write_int(self.Rare);
+        buf.write_int(self.RaceType as i32);
+        buf.write_int(self.OtherRace as i32);
+        buf.write_size(self.Attribute.len()); for _e0 in &self.Attribute { _e0.write(buf); }
+        buf.write_int(self.TriggerType);
+        buf.write_size(self.TriggerParam.len()); for _e0 in &self.TriggerParam { buf.write_int(*_e0); }
+        buf.write_int(self.EventTip);
+        buf.write_int(self.TriggerAction);
+        buf.write_size(self.TriggerValue.len()); for _e0 in &self.TriggerValue { _e0.write(buf); }
+        buf.write_size(self.OtherValue.len()); for _e0 in &self.OtherValue { buf.write_int(*_e0); }
+        buf.write_int(self.AttackSound);
+        buf.write_int(self.SelectSound);
+        buf.write_int(self.AttackParticle);
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
         self.Attribute.iter_mut().for_each(|mut x| { x.resolve_ref(tables); });
         self.TriggerValue.iter_mut().for_each(|mut x| { x.resolve_ref(tables); });
@@ -209,8 +240,10 @@ impl tbelement {
         let mut data_map: std::collections::HashMap<i32, std::sync::Arc<crate::element::Element>> = Default::default();
         let mut data_list: Vec<std::sync::Arc<crate::element::Element>> = vec![];
 
-        for x in (0..buf.read_size()).rev() {
-            let row = std::sync::Arc::new(crate::element::Element::new(&mut buf)?);
+        let n = buf.try_read_size()?;
+        for i in 0..n {
+            let offset = buf.reader_index;
+            let row = std::sync::Arc::new(crate::element::Element::new(&mut buf).map_err(|e| LubanError::Table(format!("tbelement: row {} at offset {}: {}", i, offset, e)))?);
             data_list.push(row.clone());
             data_map.insert(row.Id.clone(), row.clone());
         }
@@ -218,6 +251,13 @@ impl tbelement {
         Ok(std::sync::Arc::new(tbelement { data_map, data_list }))
     }
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_size(self.data_list.len());
+        for row in &self.data_list {
+            row.write(buf);
+        }
+    }
+
     pub fn get(&self, key: &i32) -> Option<std::sync::Arc<crate::element::Element>> {
         self.data_map.get(key).map(|x| x.clone())
     }
@@ -51,27 +51,47 @@ pub struct Enemy {
 
 impl Enemy{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<Enemy, LubanError> {
-        let Id = buf.read_int();
-        let Icon = buf.read_string();
-        let NameID = buf.read_int();
-        let DescID = buf.read_int();
-        let Map = buf.read_int();
-        let Level = buf.read_int();
-        let BOSS = buf.read_int();
-        let Hp = buf.read_int();
-        let Attack = buf.read_int();
-        let UpgradeAttack = buf.read_int();
-        let EndlessHp = buf.read_int();
-        let EndlessAttack = buf.read_int();
-        let EndlessUpgrade = buf.read_int();
-        let Gold = buf.read_int();
-        let GoldMax = buf.read_int();
-        let AttackSound = buf.read_int();
-        let ShowSound = buf.read_int();
+        let Id = buf.try_read_int()?;
+        let Icon = buf.try_read_string()?;
+        let NameID = buf.try_read_int()?;
+        let DescID = buf.try_read_int()?;
+        let Map = buf.try_read_int()?;
+        let Level = buf.try_read_int()?;
+        let BOSS = buf.try_read_int()?;
+        let Hp = buf.try_read_int()?;
+        let Attack = buf.try_read_int()?;
+        let UpgradeAttack = buf.try_read_int()?;
+        let EndlessHp = buf.try_read_int()?;
+        let EndlessAttack = buf.try_read_int()?;
+        let EndlessUpgrade = buf.try_read_int()?;
+        let Gold = buf.try_read_int()?;
+        let GoldMax = buf.try_read_int()?;
+        let AttackSound = buf.try_read_int()?;
+        let ShowSound = buf.try_read_int()?;
         
         Ok(Enemy { Id, Icon, NameID, DescID, Map, Level, BOSS, Hp, Attack, UpgradeAttack, EndlessHp, EndlessAttack, EndlessUpgrade, Gold, GoldMax, AttackSound, ShowSound, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_int(self.Id);
+        buf.write_string(&self.Icon);
+        buf.write_int(self.NameID);
+        buf.write_int(self.DescID);
+        buf.write_int(self.Map);
+        buf.write_int(self.Level);
+        buf.write_int(self.BOSS);
+        buf.write_int(self.Hp);
+        buf.write_int(self.Attack);
+        buf.write_int(self.UpgradeAttack);
+        buf.write_int(self.EndlessHp);
+        buf.write_int(self.EndlessAttack);
+        buf.write_int(self.EndlessUpgrade);
+        buf.write_int(self.Gold);
+        buf.write_int(self.GoldMax);
+        buf.write_int(self.AttackSound);
+        buf.write_int(self.ShowSound);
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
     }
 
@@ -90,8 +110,10 @@ impl tbenemy {
         let mut data_map: std::collections::HashMap<i32, std::sync::Arc<crate::enemy::Enemy>> = Default::default();
         let mut data_list: Vec<std::sync::Arc<crate::enemy::Enemy>> = vec![];
 
-        for x in (0..buf.read_size()).rev() {
-            let row = std::sync::Arc::new(crate::enemy::Enemy::new(&mut buf)?);
+        let n = buf.try_read_size()?;
+        for i in 0..n {
+            let offset = buf.reader_index;
+            let row = std::sync::Arc::new(crate::enemy::Enemy::new(&mut buf).map_err(|e| LubanError::Table(format!("tbenemy: row {} at offset {}: {}", i, offset, e)))?);
             data_list.push(row.clone());
             data_map.insert(row.Id.clone(), row.clone());
         }
@@ -99,6 +121,13 @@ impl tbenemy {
         Ok(std::sync::Arc::new(tbenemy { data_map, data_list }))
     }
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_size(self.data_list.len());
+        for row in &self.data_list {
+            row.write(buf);
+        }
+    }
+
     pub fn get(&self, key: &i32) -> Option<std::sync::Arc<crate::enemy::Enemy>> {
         self.data_map.get(key).map(|x| x.clone())
     }
@@ -18,26 +18,7 @@ pub mod prelude {
     pub use crate::relics::*;
 }
 
-#[derive(Debug)]
-pub enum LubanError {
-    Loader(String),
-    Table(String),
-    Bean(String),
-    Polymorphic(String),
-    Unknown(String),
-}
-
-impl std::fmt::Display for LubanError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(match self {
-            LubanError::Loader(msg) |
-            LubanError::Table(msg) |
-            LubanError::Bean(msg) |
-            LubanError::Polymorphic(msg) |
-            LubanError::Unknown(msg) => msg,
-        })
-    }
-}
+pub use luban_lib::LubanError;
 
 pub struct Tables{
     pub tbelement: std::sync::Arc<crate::element::tbelement>,
@@ -60,6 +41,15 @@ impl Tables {
         Ok(tables)
     }
 
+    pub fn save<T: FnMut(&str, ByteBuf) -> Result<(), LubanError>>(&self, mut writer: T) -> Result<(), LubanError> {
+        let mut buf = ByteBuf::with_capacity(0); self.tbelement.write(&mut buf); writer("element_tbelement", buf)?;
+        let mut buf = ByteBuf::with_capacity(0); self.tblocalization.write(&mut buf); writer("localization_tblocalization", buf)?;
+        let mut buf = ByteBuf::with_capacity(0); self.tbraceattribute.write(&mut buf); writer("raceattribute_tbraceattribute", buf)?;
+        let mut buf = ByteBuf::with_capacity(0); self.tbenemy.write(&mut buf); writer("enemy_tbenemy", buf)?;
+        let mut buf = ByteBuf::with_capacity(0); self.tbrelics.write(&mut buf); writer("relics_tbrelics", buf)?;
+        Ok(())
+    }
+
     unsafe fn resolve_ref(&mut self) {
         let mut b = Box::from_raw(self.tbelement.as_ref() as *const crate::element::tbelement as *mut crate::element::tbelement); b.as_mut().resolve_ref(self); let _ = Box::into_raw(b);
         let mut b = Box::from_raw(self.tblocalization.as_ref() as *const crate::localization::tblocalization as *mut crate::localization::tblocalization); b.as_mut().resolve_ref(self); let _ = Box::into_raw(b);
@@ -76,20 +66,22 @@ pub mod relics;
 
 use luban_lib::*;
 
-#[derive(Debug, Hash, Eq, PartialEq, macros::EnumFromNum)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, macros::EnumFromNum)]
 pub enum AudioType {
     UNKNOWN = 0,
     ACC = 1,
     AIFF = 2,
 }
 
-impl From<i32> for AudioType {
-    fn from(value: i32) -> Self {
+impl TryFrom<i32> for AudioType {
+    type Error = LubanError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
         match value {
-            0 => AudioType::UNKNOWN,
-            1 => AudioType::ACC,
-            2 => AudioType::AIFF,
-            _ => panic!("Invalid value for AudioType:{}", value),
+            0 => Ok(AudioType::UNKNOWN),
+            1 => Ok(AudioType::ACC),
+            2 => Ok(AudioType::AIFF),
+            _ => Err(LubanError::Bean(format!("Invalid value for AudioType:{}", value))),
         }
     }
 }
@@ -102,12 +94,17 @@ pub struct ElementAttribute {
 
 impl ElementAttribute{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<ElementAttribute, LubanError> {
-        let ID = buf.read_int();
-        let Value = buf.read_int();
+        let ID = buf.try_read_int()?;
+        let Value = buf.try_read_int()?;
         
         Ok(ElementAttribute { ID, Value, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_int(self.ID);
+        buf.write_int(self.Value);
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
     }
 
@@ -121,11 +118,15 @@ pub struct ElementTriggerValue {
 
 impl ElementTriggerValue{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<ElementTriggerValue, LubanError> {
-        let Value = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.read_int()); } _e0 };
+        let Value = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.try_read_int()?); } _e0 };
         
         Ok(ElementTriggerValue { Value, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_size(self.Value.len()); for _e0 in &self.Value { buf.write_int(*_e0); }
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
     }
 
@@ -140,12 +141,17 @@ pub struct UnlockCondition {
 
 impl UnlockCondition{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<UnlockCondition, LubanError> {
-        let UnlockType = buf.read_int();
-        let Condition = buf.read_int();
+        let UnlockType = buf.try_read_int()?;
+        let Condition = buf.try_read_int()?;
         
         Ok(UnlockCondition { UnlockType, Condition, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_int(self.UnlockType);
+        buf.write_int(self.Condition);
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
     }
 
@@ -160,12 +166,17 @@ pub struct vec2 {
 
 impl vec2{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<vec2, LubanError> {
-        let x = buf.read_float();
-        let y = buf.read_float();
+        let x = buf.try_read_float()?;
+        let y = buf.try_read_float()?;
         
         Ok(vec2 { x, y, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_float(self.x);
+        buf.write_float(self.y);
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
     }
 
@@ -181,13 +192,19 @@ pub struct vec3 {
 
 impl vec3{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<vec3, LubanError> {
-        let x = buf.read_float();
-        let y = buf.read_float();
-        let z = buf.read_float();
+        let x = buf.try_read_float()?;
+        let y = buf.try_read_float()?;
+        let z = buf.try_read_float()?;
         
         Ok(vec3 { x, y, z, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_float(self.x);
+        buf.write_float(self.y);
+        buf.write_float(self.z);
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
     }
 
@@ -204,18 +221,206 @@ pub struct vec4 {
 
 impl vec4{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<vec4, LubanError> {
-        let x = buf.read_float();
-        let y = buf.read_float();
-        let z = buf.read_float();
-        let w = buf.read_float();
+        let x = buf.try_read_float()?;
+        let y = buf.try_read_float()?;
+        let z = buf.try_read_float()?;
+        let w = buf.try_read_float()?;
         
         Ok(vec4 { x, y, z, w, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_float(self.x);
+        buf.write_float(self.y);
+        buf.write_float(self.z);
+        buf.write_float(self.w);
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
     }
 
     pub const __ID__: i32 = 3615520;
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::*;
+
+    fn empty_table() -> ByteBuf {
+        let mut buf = ByteBuf::with_capacity(1);
+        buf.write_size(0);
+        buf
+    }
+
+    fn load_with_relics(relics: ByteBuf) -> Result<Tables, LubanError> {
+        Tables::new(|name| {
+            if name == "relics_tbrelics" {
+                Ok(ByteBuf::new(relics.copy_data()))
+            } else {
+                Ok(empty_table())
+            }
+        })
+    }
+
+    fn write_relic(buf: &mut ByteBuf, id: i32, tip: i32) {
+        buf.write_int(id);
+        for _ in 0..4 {
+            buf.write_int(0);
+        }
+        buf.write_string("icon");
+        buf.write_string("small_icon");
+        for _ in 0..3 {
+            buf.write_int(0);
+        }
+        buf.write_size(1);
+        buf.write_int(tip);
+        buf.write_int(0);
+        buf.write_size(0);
+        buf.write_int(0);
+        buf.write_size(0);
+        buf.write_int(0);
+        buf.write_int(0);
+        buf.write_size(0);
+        buf.write_size(0);
+    }
+
+    fn write_element(buf: &mut ByteBuf) {
+        Element {
+            Id: 1001,
+            Lock: 0,
+            Enable: 1,
+            Role: -1,
+            name_id: 20001,
+            desc_id: 20002,
+            desctip: vec![Etip::Sum, Etip::Prob],
+            DescAttribute: vec![3, 300000],
+            Icon: "猫猫".to_string(),
+            Rare: 2,
+            RaceType: ERaceType::Cat,
+            OtherRace: ERaceType::Mod8,
+            Attribute: vec![ElementAttribute { ID: 1, Value: 10 }, ElementAttribute { ID: 2, Value: -5 }],
+            TriggerType: 4,
+            TriggerParam: vec![],
+            EventTip: 0,
+            TriggerAction: 7,
+            TriggerValue: vec![ElementTriggerValue { Value: vec![1, 2, 3] }, ElementTriggerValue { Value: vec![] }],
+            OtherValue: vec![i32::MIN, i32::MAX],
+            AttackSound: 1,
+            SelectSound: 2,
+            AttackParticle: 3,
+        }
+        .write(buf);
+    }
+
+    fn sample_tables() -> std::collections::HashMap<String, Vec<u8>> {
+        let mut files = std::collections::HashMap::new();
+
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_size(1);
+        write_element(&mut buf);
+        files.insert("element_tbelement".to_string(), buf.copy_data());
+
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_size(2);
+        write_relic(&mut buf, 1, 1);
+        write_relic(&mut buf, 1000000, 5);
+        files.insert("relics_tbrelics".to_string(), buf.copy_data());
+
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_size(1);
+        crate::localization::Localization {
+            Id: 20001,
+            CN: "猫".to_string(),
+            EN: "Cat".to_string(),
+            JP: "ねこ".to_string(),
+            CNT: "貓".to_string(),
+        }
+        .write(&mut buf);
+        files.insert("localization_tblocalization".to_string(), buf.copy_data());
+
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_size(1);
+        crate::raceattribute::RaceAttribute { Id: 14, Icon: "mod1".to_string(), NameID: 1, DescID: 2 }.write(&mut buf);
+        files.insert("raceattribute_tbraceattribute".to_string(), buf.copy_data());
+
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_size(1);
+        crate::enemy::Enemy {
+            Id: 1, Icon: "slime".to_string(), NameID: 1, DescID: 2, Map: 1, Level: 3, BOSS: 1,
+            Hp: 100, Attack: 10, UpgradeAttack: 2, EndlessHp: 1000, EndlessAttack: 100, EndlessUpgrade: 20,
+            Gold: 5, GoldMax: 10, AttackSound: 0, ShowSound: 0,
+        }
+        .write(&mut buf);
+        files.insert("enemy_tbenemy".to_string(), buf.copy_data());
+
+        files
+    }
+
+    #[test]
+    fn tables_save_round_trips_bytes() {
+        let files = sample_tables();
+        let tables = Tables::new(|name| Ok(ByteBuf::new(files[name].clone()))).unwrap();
+        assert_eq!(tables.tbelement[1001].TriggerValue[0].Value, vec![1, 2, 3]);
+
+        let mut saved = std::collections::HashMap::new();
+        tables
+            .save(|name, buf| {
+                saved.insert(name.to_string(), buf.copy_data());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(saved, files);
+    }
+
+    #[test]
+    fn tables_save_propagates_writer_error() {
+        let files = sample_tables();
+        let tables = Tables::new(|name| Ok(ByteBuf::new(files[name].clone()))).unwrap();
+        let err = tables.save(|name, _| Err(LubanError::Loader(name.to_string())));
+        assert_eq!(err.err().unwrap().to_string(), "element_tbelement");
+    }
+
+    #[test]
+    fn enums_reject_unknown_values() {
+        assert_eq!(ERaceType::try_from(21).unwrap(), ERaceType::Mod8);
+        assert!(ERaceType::try_from(22).is_err());
+        assert!(ERare::try_from(-1).is_err());
+        assert!(Etip::try_from(6u8).is_err());
+        assert!(AudioType::try_from(3i64).is_err());
+    }
+
+    #[test]
+    fn tables_load_valid_data() {
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_size(2);
+        write_relic(&mut buf, 1, 1);
+        write_relic(&mut buf, 2, 5);
+        let tables = load_with_relics(buf).unwrap();
+        assert_eq!(tables.tbrelics.data_list.len(), 2);
+        assert_eq!(tables.tbrelics[2].DescTip, vec![Etip::Near]);
+    }
+
+    #[test]
+    fn tables_report_truncated_row() {
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_size(2);
+        write_relic(&mut buf, 1, 1);
+        let offset = buf.writer_index;
+        buf.write_int(2);
+        let err = load_with_relics(buf).err().unwrap().to_string();
+        assert!(err.contains("tbrelics"), "{}", err);
+        assert!(err.contains("row 1"), "{}", err);
+        assert!(err.contains(&format!("offset {}", offset)), "{}", err);
+    }
 
+    #[test]
+    fn tables_report_invalid_enum() {
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_size(1);
+        write_relic(&mut buf, 1, 99);
+        let err = load_with_relics(buf).err().unwrap().to_string();
+        assert!(err.contains("row 0"), "{}", err);
+        assert!(err.contains("Invalid value for Etip:99"), "{}", err);
+    }
+}
@@ -23,15 +23,23 @@ pub struct Localization {
 
 impl Localization{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<Localization, LubanError> {
-        let Id = buf.read_int();
-        let CN = buf.read_string();
-        let EN = buf.read_string();
-        let JP = buf.read_string();
-        let CNT = buf.read_string();
+        let Id = buf.try_read_int()?;
+        let CN = buf.try_read_string()?;
+        let EN = buf.try_read_string()?;
+        let JP = buf.try_read_string()?;
+        let CNT = buf.try_read_string()?;
         
         Ok(Localization { Id, CN, EN, JP, CNT, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_int(self.Id);
+        buf.write_string(&self.CN);
+        buf.write_string(&self.EN);
+        buf.write_string(&self.JP);
+        buf.write_string(&self.CNT);
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
     }
 
@@ -50,8 +58,10 @@ impl tblocalization {
         let mut data_map: std::collections::HashMap<i32, std::sync::Arc<crate::localization::Localization>> = Default::default();
         let mut data_list: Vec<std::sync::Arc<crate::localization::Localization>> = vec![];
 
-        for x in (0..buf.read_size()).rev() {
-            let row = std::sync::Arc::new(crate::localization::Localization::new(&mut buf)?);
+        let n = buf.try_read_size()?;
+        for i in 0..n {
+            let offset = buf.reader_index;
+            let row = std::sync::Arc::new(crate::localization::Localization::new(&mut buf).map_err(|e| LubanError::Table(format!("tblocalization: row {} at offset {}: {}", i, offset, e)))?);
             data_list.push(row.clone());
             data_map.insert(row.Id.clone(), row.clone());
         }
@@ -59,6 +69,13 @@ impl tblocalization {
         Ok(std::sync::Arc::new(tblocalization { data_map, data_list }))
     }
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_size(self.data_list.len());
+        for row in &self.data_list {
+            row.write(buf);
+        }
+    }
+
     pub fn get(&self, key: &i32) -> Option<std::sync::Arc<crate::localization::Localization>> {
         self.data_map.get(key).map(|x| x.clone())
     }
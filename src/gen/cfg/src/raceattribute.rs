@@ -25,14 +25,21 @@ pub struct RaceAttribute {
 
 impl RaceAttribute{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<RaceAttribute, LubanError> {
-        let Id = buf.read_int();
-        let Icon = buf.read_string();
-        let NameID = buf.read_int();
-        let DescID = buf.read_int();
+        let Id = buf.try_read_int()?;
+        let Icon = buf.try_read_string()?;
+        let NameID = buf.try_read_int()?;
+        let DescID = buf.try_read_int()?;
         
         Ok(RaceAttribute { Id, Icon, NameID, DescID, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_int(self.Id);
+        buf.write_string(&self.Icon);
+        buf.write_int(self.NameID);
+        buf.write_int(self.DescID);
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
     }
 
@@ -51,8 +58,10 @@ impl tbraceattribute {
         let mut data_map: std::collections::HashMap<i32, std::sync::Arc<crate::raceattribute::RaceAttribute>> = Default::default();
         let mut data_list: Vec<std::sync::Arc<crate::raceattribute::RaceAttribute>> = vec![];
 
-        for x in (0..buf.read_size()).rev() {
-            let row = std::sync::Arc::new(crate::raceattribute::RaceAttribute::new(&mut buf)?);
+        let n = buf.try_read_size()?;
+        for i in 0..n {
+            let offset = buf.reader_index;
+            let row = std::sync::Arc::new(crate::raceattribute::RaceAttribute::new(&mut buf).map_err(|e| LubanError::Table(format!("tbraceattribute: row {} at offset {}: {}", i, offset, e)))?);
             data_list.push(row.clone());
             data_map.insert(row.Id.clone(), row.clone());
         }
@@ -60,6 +69,13 @@ impl tbraceattribute {
         Ok(std::sync::Arc::new(tbraceattribute { data_map, data_list }))
     }
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_size(self.data_list.len());
+        for row in &self.data_list {
+            row.write(buf);
+        }
+    }
+
     pub fn get(&self, key: &i32) -> Option<std::sync::Arc<crate::raceattribute::RaceAttribute>> {
         self.data_map.get(key).map(|x| x.clone())
     }
@@ -55,29 +55,51 @@ pub struct Relics {
 
 impl Relics{
     pub(crate) fn new(mut buf: &mut ByteBuf) -> Result<Relics, LubanError> {
-        let Id = buf.read_int();
-        let Enable = buf.read_int();
-        let Lock = buf.read_int();
-        let role = buf.read_int();
-        let tutorial = buf.read_int();
-        let Icon = buf.read_string();
-        let SmallIcon = buf.read_string();
-        let NameID = buf.read_int();
-        let DescID = buf.read_int();
-        let OtherDescID = buf.read_int();
-        let DescTip = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.read_int().into()); } _e0 };
-        let Rare = buf.read_int();
-        let Passive = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.read_int()); } _e0 };
-        let TriggerType = buf.read_int();
-        let TriggerParam = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.read_int()); } _e0 };
-        let EventTip = buf.read_int();
-        let TriggerAction = buf.read_int();
-        let TriggerValue = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.read_int()); } _e0 };
-        let OtherValue = {let n0 = std::cmp::min(buf.read_size(), buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.read_int()); } _e0 };
+        let Id = buf.try_read_int()?;
+        let Enable = buf.try_read_int()?;
+        let Lock = buf.try_read_int()?;
+        let role = buf.try_read_int()?;
+        let tutorial = buf.try_read_int()?;
+        let Icon = buf.try_read_string()?;
+        let SmallIcon = buf.try_read_string()?;
+        let NameID = buf.try_read_int()?;
+        let DescID = buf.try_read_int()?;
+        let OtherDescID = buf.try_read_int()?;
+        let DescTip = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.try_read_int()?.try_into()?); } _e0 };
+        let Rare = buf.try_read_int()?;
+        let Passive = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.try_read_int()?); } _e0 };
+        let TriggerType = buf.try_read_int()?;
+        let TriggerParam = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.try_read_int()?); } _e0 };
+        let EventTip = buf.try_read_int()?;
+        let TriggerAction = buf.try_read_int()?;
+        let TriggerValue = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.try_read_int()?); } _e0 };
+        let OtherValue = {let n0 = std::cmp::min(buf.try_read_size()?, buf.size());let mut _e0 = vec![]; for i0 in 0..n0 { _e0.push(buf.try_read_int()?); } _e0 };
         
         Ok(Relics { Id, Enable, Lock, role, tutorial, Icon, SmallIcon, NameID, DescID, OtherDescID, DescTip, Rare, Passive, TriggerType, TriggerParam, EventTip, TriggerAction, TriggerValue, OtherValue, })
     }    
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_int(self.Id);
+        buf.write_int(self.Enable);
+        buf.write_int(self.Lock);
+        buf.write_int(self.role);
+        buf.write_int(self.tutorial);
+        buf.write_string(&self.Icon);
+        buf.write_string(&self.SmallIcon);
+        buf.write_int(self.NameID);
+        buf.write_int(self.DescID);
+        buf.write_int(self.OtherDescID);
+        buf.write_size(self.DescTip.len()); for _e0 in &self.DescTip { buf.write_int(*_e0 as i32); }
+        buf.write_int(self.Rare);
+        buf.write_size(self.Passive.len()); for _e0 in &self.Passive { buf.write_int(*_e0); }
+        buf.write_int(self.TriggerType);
+        buf.write_size(self.TriggerParam.len()); for _e0 in &self.TriggerParam { buf.write_int(*_e0); }
+        buf.write_int(self.EventTip);
+        buf.write_int(self.TriggerAction);
+        buf.write_size(self.TriggerValue.len()); for _e0 in &self.TriggerValue { buf.write_int(*_e0); }
+        buf.write_size(self.OtherValue.len()); for _e0 in &self.OtherValue { buf.write_int(*_e0); }
+    }
+
     pub(crate) unsafe fn resolve_ref(&mut self, tables: &Tables) {
     }
 
@@ -96,8 +118,10 @@ impl tbrelics {
         let mut data_map: std::collections::HashMap<i32, std::sync::Arc<crate::relics::Relics>> = Default::default();
         let mut data_list: Vec<std::sync::Arc<crate::relics::Relics>> = vec![];
 
-        for x in (0..buf.read_size()).rev() {
-            let row = std::sync::Arc::new(crate::relics::Relics::new(&mut buf)?);
+        let n = buf.try_read_size()?;
+        for i in 0..n {
+            let offset = buf.reader_index;
+            let row = std::sync::Arc::new(crate::relics::Relics::new(&mut buf).map_err(|e| LubanError::Table(format!("tbrelics: row {} at offset {}: {}", i, offset, e)))?);
             data_list.push(row.clone());
             data_map.insert(row.Id.clone(), row.clone());
         }
@@ -105,6 +129,13 @@ impl tbrelics {
         Ok(std::sync::Arc::new(tbrelics { data_map, data_list }))
     }
 
+    pub fn write(&self, buf: &mut ByteBuf) {
+        buf.write_size(self.data_list.len());
+        for row in &self.data_list {
+            row.write(buf);
+        }
+    }
+
     pub fn get(&self, key: &i32) -> Option<std::sync::Arc<crate::relics::Relics>> {
         self.data_map.get(key).map(|x| x.clone())
     }
@@ -1,3 +1,26 @@
+#[derive(Debug)]
+pub enum LubanError {
+    Loader(String),
+    Table(String),
+    Bean(String),
+    Polymorphic(String),
+    Unknown(String),
+}
+
+impl std::fmt::Display for LubanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LubanError::Loader(msg) |
+            LubanError::Table(msg) |
+            LubanError::Bean(msg) |
+            LubanError::Polymorphic(msg) |
+            LubanError::Unknown(msg) => msg,
+        })
+    }
+}
+
+impl std::error::Error for LubanError {}
+
 pub struct ByteBuf {
     pub reader_index: usize,
     pub writer_index: usize,
@@ -40,153 +63,200 @@ impl ByteBuf {
     }
 
     pub fn read_bool(&mut self) -> bool {
-        self.ensure_read(1);
+        self.try_read_bool().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn read_byte(&mut self) -> u8 {
+        self.try_read_byte().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn read_short(&mut self) -> i16 {
+        self.try_read_short().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn read_uint(&mut self) -> u32 {
+        self.try_read_uint().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn read_int(&mut self) -> i32 {
+        self.try_read_int().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn read_ulong(&mut self) -> u64 {
+        self.try_read_ulong().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn read_long(&mut self) -> i64 {
+        self.try_read_long().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn read_float(&mut self) -> f32 {
+        self.try_read_float().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn read_double(&mut self) -> f64 {
+        self.try_read_double().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn read_size(&mut self) -> usize {
+        self.try_read_size().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn read_string(&mut self) -> String {
+        self.try_read_string().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_read_bool(&mut self) -> Result<bool, LubanError> {
+        self.ensure_read(1)?;
         let result = self.bytes[self.reader_index] != 0;
         self.reader_index += 1;
-        result
+        Ok(result)
     }
 
-    pub fn read_byte(&mut self) -> u8 {
-        self.ensure_read(1);
+    pub fn try_read_byte(&mut self) -> Result<u8, LubanError> {
+        self.ensure_read(1)?;
         let result = self.bytes[self.reader_index];
         self.reader_index += 1;
-        result
+        Ok(result)
     }
 
-    pub fn read_short(&mut self) -> i16 {
-        self.ensure_read(1);
+    pub fn try_read_short(&mut self) -> Result<i16, LubanError> {
+        self.ensure_read(1)?;
         let h = self.bytes[self.reader_index];
         if h < 0x80 {
             self.reader_index += 1;
-            return h as i16;
+            return Ok(h as i16);
         }
 
         if h < 0xc0 {
-            self.ensure_read(2);
+            self.ensure_read(2)?;
             let x = (((h & 0x3f) as i16) << 8) | (self.bytes[self.reader_index + 1] as i16);
             self.reader_index += 2;
-            return x;
+            return Ok(x);
         }
 
         if h < 0xff {
-            self.ensure_read(3);
+            self.ensure_read(3)?;
             let x = ((self.bytes[self.reader_index + 1] as i16) << 8)
                 | (self.bytes[self.reader_index + 2] as i16);
             self.reader_index += 3;
-            return x;
+            return Ok(x);
         }
 
-        panic!("Invalid data")
+        Err(LubanError::Bean(format!(
+            "invalid short header {:#x} at offset {}",
+            h, self.reader_index
+        )))
     }
 
-    pub fn read_uint(&mut self) -> u32 {
-        self.ensure_read(1);
+    pub fn try_read_uint(&mut self) -> Result<u32, LubanError> {
+        self.ensure_read(1)?;
         let h = self.bytes[self.reader_index] as u32;
         if h < 0x80 {
             self.reader_index += 1;
-            return h;
+            return Ok(h);
         }
         if h < 0xc0 {
-            self.ensure_read(2);
+            self.ensure_read(2)?;
             let x = ((h & 0x3f) << 8) | (self.bytes[self.reader_index + 1] as u32);
             self.reader_index += 2;
-            return x;
+            return Ok(x);
         }
         if h < 0xe0 {
-            self.ensure_read(3);
+            self.ensure_read(3)?;
             let x = ((h & 0x1f) << 16)
                 | ((self.bytes[self.reader_index + 1] as u32) << 8)
                 | (self.bytes[self.reader_index + 2] as u32);
             self.reader_index += 3;
-            return x;
+            return Ok(x);
         }
         if h < 0xf0 {
-            self.ensure_read(4);
+            self.ensure_read(4)?;
             let x = ((h & 0x0f) << 24)
                 | ((self.bytes[self.reader_index + 1] as u32) << 16)
                 | ((self.bytes[self.reader_index + 2] as u32) << 8)
                 | (self.bytes[self.reader_index + 3] as u32);
             self.reader_index += 4;
-            return x;
+            return Ok(x);
         } else {
-            self.ensure_read(5);
+            self.ensure_read(5)?;
             let x = ((self.bytes[self.reader_index + 1] as u32) << 24)
                 | ((self.bytes[self.reader_index + 2] as u32) << 16)
                 | ((self.bytes[self.reader_index + 3] as u32) << 8)
                 | (self.bytes[self.reader_index + 4] as u32);
             self.reader_index += 5;
-            return x;
+            return Ok(x);
         }
     }
 
-    pub fn read_int(&mut self) -> i32 {
-        self.read_uint() as i32
+    pub fn try_read_int(&mut self) -> Result<i32, LubanError> {
+        Ok(self.try_read_uint()? as i32)
     }
 
-    pub fn read_ulong(&mut self) -> u64 {
-        self.ensure_read(1);
+    pub fn try_read_ulong(&mut self) -> Result<u64, LubanError> {
+        self.ensure_read(1)?;
         let h = self.bytes[self.reader_index];
         if h < 0x80 {
             self.reader_index += 1;
-            return h as u64;
+            return Ok(h as u64);
         }
         if h < 0xc0 {
-            self.ensure_read(2);
+            self.ensure_read(2)?;
             let x = (((h & 0x3f) as u64) << 8) | (self.bytes[self.reader_index + 1] as u64);
             self.reader_index += 2;
-            return x;
+            return Ok(x);
         }
         if h < 0xe0 {
-            self.ensure_read(3);
+            self.ensure_read(3)?;
             let x = (((h & 0x1f) as u64) << 16)
                 | ((self.bytes[self.reader_index + 1] as u64) << 8)
                 | (self.bytes[self.reader_index + 2] as u64);
             self.reader_index += 3;
-            return x;
+            return Ok(x);
         }
         if h < 0xf0 {
-            self.ensure_read(4);
+            self.ensure_read(4)?;
             let x = (((h & 0x0f) as u64) << 24)
                 | ((self.bytes[self.reader_index + 1] as u64) << 16)
                 | ((self.bytes[self.reader_index + 2] as u64) << 8)
                 | (self.bytes[self.reader_index + 3] as u64);
             self.reader_index += 4;
-            return x;
+            return Ok(x);
         }
         if h < 0xf8 {
-            self.ensure_read(5);
+            self.ensure_read(5)?;
             let xl = ((self.bytes[self.reader_index + 1] as u64) << 24)
                 | ((self.bytes[self.reader_index + 2] as u64) << 16)
                 | ((self.bytes[self.reader_index + 3] as u64) << 8)
                 | (self.bytes[self.reader_index + 4] as u64);
             let xh = (h & 0x07) as u64;
             self.reader_index += 5;
-            return (xh << 32) | xl;
+            return Ok((xh << 32) | xl);
         }
         if h < 0xfc {
-            self.ensure_read(6);
+            self.ensure_read(6)?;
             let xl = ((self.bytes[self.reader_index + 2] as u64) << 24)
                 | ((self.bytes[self.reader_index + 3] as u64) << 16)
                 | ((self.bytes[self.reader_index + 4] as u64) << 8)
                 | (self.bytes[self.reader_index + 5] as u64);
             let xh = (((h & 0x03) as u64) << 8) | (self.bytes[self.reader_index + 1] as u64);
             self.reader_index += 6;
-            return (xh << 32) | xl;
+            return Ok((xh << 32) | xl);
         }
         if h < 0xfe {
-            self.ensure_read(7);
+            self.ensure_read(7)?;
             let xl = ((self.bytes[self.reader_index + 3] as u64) << 24)
                 | ((self.bytes[self.reader_index + 4] as u64) << 16)
                 | ((self.bytes[self.reader_index + 5] as u64) << 8)
                 | (self.bytes[self.reader_index + 6] as u64);
             let xh = (((h & 0x01) as u64) << 16)
                 | ((self.bytes[self.reader_index + 1] as u64) << 8)
-                | (self.bytes[self.reader_index + 1] as u64);
+                | (self.bytes[self.reader_index + 2] as u64);
             self.reader_index += 7;
-            return (xh << 32) | xl;
+            return Ok((xh << 32) | xl);
         }
         if h < 0xff {
-            self.ensure_read(8);
+            self.ensure_read(8)?;
             let xl = ((self.bytes[self.reader_index + 4] as u64) << 24)
                 | ((self.bytes[self.reader_index + 5] as u64) << 16)
                 | ((self.bytes[self.reader_index + 6] as u64) << 8)
@@ -195,9 +265,9 @@ impl ByteBuf {
                 | ((self.bytes[self.reader_index + 2] as u64) << 8)
                 | (self.bytes[self.reader_index + 3] as u64);
             self.reader_index += 8;
-            return (xh << 32) | xl;
+            return Ok((xh << 32) | xl);
         } else {
-            self.ensure_read(9);
+            self.ensure_read(9)?;
             let xl = ((self.bytes[self.reader_index + 5] as u64) << 24)
                 | ((self.bytes[self.reader_index + 6] as u64) << 16)
                 | ((self.bytes[self.reader_index + 7] as u64) << 8)
@@ -207,49 +277,158 @@ impl ByteBuf {
                 | ((self.bytes[self.reader_index + 3] as u64) << 8)
                 | (self.bytes[self.reader_index + 4] as u64);
             self.reader_index += 9;
-            return (xh << 32) | xl;
+            return Ok((xh << 32) | xl);
         }
     }
 
-    pub fn read_long(&mut self) -> i64 {
-        self.read_ulong() as i64
+    pub fn try_read_long(&mut self) -> Result<i64, LubanError> {
+        Ok(self.try_read_ulong()? as i64)
     }
 
-    pub fn read_float(&mut self) -> f32 {
-        self.ensure_read(4);
+    pub fn try_read_float(&mut self) -> Result<f32, LubanError> {
+        self.ensure_read(4)?;
         let buf = self.bytes[self.reader_index..].first_chunk::<4>().unwrap();
         self.reader_index += 4;
-        f32::from_le_bytes(*buf)
+        Ok(f32::from_le_bytes(*buf))
     }
 
-    pub fn read_double(&mut self) -> f64 {
-        self.ensure_read(8);
+    pub fn try_read_double(&mut self) -> Result<f64, LubanError> {
+        self.ensure_read(8)?;
         let buf = self.bytes[self.reader_index..].first_chunk::<8>().unwrap();
         self.reader_index += 8;
-        f64::from_le_bytes(*buf)
+        Ok(f64::from_le_bytes(*buf))
     }
 
-    pub fn read_size(&mut self) -> usize {
-        self.read_uint() as usize
+    pub fn try_read_size(&mut self) -> Result<usize, LubanError> {
+        Ok(self.try_read_uint()? as usize)
     }
 
-    pub fn read_string(&mut self) -> String {
-        let n = self.read_size();
+    pub fn try_read_string(&mut self) -> Result<String, LubanError> {
+        let n = self.try_read_size()?;
         if n > 0 {
-            self.ensure_read(n);
+            self.ensure_read(n)?;
             let s = String::from_utf8_lossy(&self.bytes[self.reader_index..self.reader_index + n]);
             self.reader_index += n;
-            return s.to_string();
+            return Ok(s.to_string());
+        }
+
+        Ok("".to_string())
+    }
+
+    pub fn write_bool(&mut self, x: bool) {
+        self.write_byte(if x { 1 } else { 0 });
+    }
+
+    pub fn write_byte(&mut self, x: u8) {
+        self.ensure_write(1);
+        self.bytes[self.writer_index] = x;
+        self.writer_index += 1;
+    }
+
+    pub fn write_uint(&mut self, x: u32) {
+        if x < 0x80 {
+            self.write_raw(&[x as u8]);
+        } else if x < 0x4000 {
+            self.write_raw(&[((x >> 8) | 0x80) as u8, x as u8]);
+        } else if x < 0x200000 {
+            self.write_raw(&[((x >> 16) | 0xc0) as u8, (x >> 8) as u8, x as u8]);
+        } else if x < 0x10000000 {
+            self.write_raw(&[
+                ((x >> 24) | 0xe0) as u8,
+                (x >> 16) as u8,
+                (x >> 8) as u8,
+                x as u8,
+            ]);
+        } else {
+            self.write_raw(&[
+                0xf0,
+                (x >> 24) as u8,
+                (x >> 16) as u8,
+                (x >> 8) as u8,
+                x as u8,
+            ]);
         }
+    }
 
-        "".to_string()
+    pub fn write_int(&mut self, x: i32) {
+        self.write_uint(x as u32)
     }
+
+    pub fn write_ulong(&mut self, x: u64) {
+        if x < 0x10000000 {
+            self.write_uint(x as u32);
+            return;
+        }
+        let xl = x as u32;
+        let low = [(xl >> 24) as u8, (xl >> 16) as u8, (xl >> 8) as u8, xl as u8];
+        if x < 0x800000000 {
+            self.write_raw(&[((x >> 32) | 0xf0) as u8]);
+        } else if x < 0x40000000000 {
+            self.write_raw(&[((x >> 40) | 0xf8) as u8, (x >> 32) as u8]);
+        } else if x < 0x2000000000000 {
+            self.write_raw(&[((x >> 48) | 0xfc) as u8, (x >> 40) as u8, (x >> 32) as u8]);
+        } else if x < 0x100000000000000 {
+            self.write_raw(&[0xfe, (x >> 48) as u8, (x >> 40) as u8, (x >> 32) as u8]);
+        } else {
+            self.write_raw(&[
+                0xff,
+                (x >> 56) as u8,
+                (x >> 48) as u8,
+                (x >> 40) as u8,
+                (x >> 32) as u8,
+            ]);
+        }
+        self.write_raw(&low);
+    }
+
+    pub fn write_long(&mut self, x: i64) {
+        self.write_ulong(x as u64)
+    }
+
+    pub fn write_float(&mut self, x: f32) {
+        self.write_raw(&x.to_le_bytes());
+    }
+
+    pub fn write_double(&mut self, x: f64) {
+        self.write_raw(&x.to_le_bytes());
+    }
+
+    pub fn write_size(&mut self, n: usize) {
+        self.write_uint(n as u32)
+    }
+
+    pub fn write_string(&mut self, x: &str) {
+        self.write_size(x.len());
+        self.write_raw(x.as_bytes());
+    }
+
     //region internal
     #[inline]
-    fn ensure_read(&self, size: usize) {
+    fn ensure_read(&self, size: usize) -> Result<(), LubanError> {
         if self.reader_index + size > self.writer_index {
-            panic!("Not enough data")
+            return Err(LubanError::Bean(format!(
+                "Not enough data: need {} bytes at offset {}, {} remaining",
+                size,
+                self.reader_index,
+                self.writer_index.saturating_sub(self.reader_index)
+            )));
         }
+        Ok(())
+    }
+
+    #[inline]
+    fn ensure_write(&mut self, size: usize) {
+        let end = self.writer_index + size;
+        if end > self.bytes.len() {
+            self.bytes.resize(end, 0);
+        }
+    }
+
+    #[inline]
+    fn write_raw(&mut self, data: &[u8]) {
+        self.ensure_write(data.len());
+        self.bytes[self.writer_index..self.writer_index + data.len()].copy_from_slice(data);
+        self.writer_index += data.len();
     }
     //endregion
 }
@@ -271,3 +450,143 @@ impl PartialEq<Self> for ByteBuf {
 }
 
 impl Eq for ByteBuf {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bool_round_trip() {
+        let mut buf = ByteBuf::with_capacity(2);
+        buf.write_bool(true);
+        buf.write_bool(false);
+        assert!(buf.read_bool());
+        assert!(!buf.read_bool());
+        assert_eq!(buf.size(), 0);
+    }
+
+    #[test]
+    fn uint_round_trip() {
+        let values = [
+            0u32, 1, 0x7f, 0x80, 0x3fff, 0x4000, 0x1fffff, 0x200000, 0xfffffff, 0x10000000,
+            u32::MAX,
+        ];
+        let mut buf = ByteBuf::with_capacity(0);
+        for x in values {
+            buf.write_uint(x);
+        }
+        for x in values {
+            assert_eq!(buf.read_uint(), x);
+        }
+        assert_eq!(buf.size(), 0);
+    }
+
+    #[test]
+    fn uint_encoded_length() {
+        for (x, len) in [(0x7fu32, 1), (0x80, 2), (0x4000, 3), (0x200000, 4), (0x10000000, 5)] {
+            let mut buf = ByteBuf::with_capacity(0);
+            buf.write_uint(x);
+            assert_eq!(buf.size(), len, "{:#x}", x);
+        }
+    }
+
+    #[test]
+    fn int_round_trip() {
+        let values = [0, 1, -1, 127, 128, -128, 1000000, i32::MIN, i32::MAX];
+        let mut buf = ByteBuf::with_capacity(0);
+        for x in values {
+            buf.write_int(x);
+        }
+        for x in values {
+            assert_eq!(buf.read_int(), x);
+        }
+        assert_eq!(buf.size(), 0);
+    }
+
+    #[test]
+    fn long_round_trip() {
+        let mut values = vec![0i64, -1, i64::MIN, i64::MAX];
+        for shift in 0..64 {
+            values.push((1u64 << shift) as i64);
+            values.push(((1u64 << shift) - 1) as i64);
+        }
+        let mut buf = ByteBuf::with_capacity(0);
+        for &x in &values {
+            buf.write_long(x);
+        }
+        for &x in &values {
+            assert_eq!(buf.read_long(), x);
+        }
+        assert_eq!(buf.size(), 0);
+    }
+
+    #[test]
+    fn float_and_double_round_trip() {
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_float(3.5);
+        buf.write_float(f32::MIN_POSITIVE);
+        buf.write_double(-0.125);
+        buf.write_double(f64::MAX);
+        assert_eq!(buf.read_float(), 3.5);
+        assert_eq!(buf.read_float(), f32::MIN_POSITIVE);
+        assert_eq!(buf.read_double(), -0.125);
+        assert_eq!(buf.read_double(), f64::MAX);
+        assert_eq!(buf.size(), 0);
+    }
+
+    #[test]
+    fn size_and_string_round_trip() {
+        let long = "x".repeat(300);
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_size(12345);
+        buf.write_string("");
+        buf.write_string("轮盘英雄");
+        buf.write_string(&long);
+        assert_eq!(buf.read_size(), 12345);
+        assert_eq!(buf.read_string(), "");
+        assert_eq!(buf.read_string(), "轮盘英雄");
+        assert_eq!(buf.read_string(), long);
+        assert_eq!(buf.size(), 0);
+    }
+
+    #[test]
+    fn write_appends_after_existing_data() {
+        let mut buf = ByteBuf::new(vec![5]);
+        buf.write_int(300);
+        assert_eq!(buf.read_byte(), 5);
+        assert_eq!(buf.read_int(), 300);
+    }
+
+    #[test]
+    fn try_read_reports_truncated_data() {
+        let mut buf = ByteBuf::new(vec![0x81]);
+        let err = buf.try_read_int().unwrap_err();
+        assert!(err.to_string().contains("offset 0"), "{}", err);
+        assert_eq!(buf.reader_index, 0);
+
+        let mut buf = ByteBuf::new(vec![1, 2, 3]);
+        assert!(buf.try_read_float().is_err());
+        assert!(buf.try_read_double().is_err());
+
+        let mut buf = ByteBuf::with_capacity(0);
+        buf.write_size(10);
+        buf.write_raw(b"abc");
+        assert!(buf.try_read_string().is_err());
+
+        let mut buf = ByteBuf::with_capacity(0);
+        assert!(buf.try_read_bool().is_err());
+        assert!(buf.try_read_long().is_err());
+    }
+
+    #[test]
+    fn try_read_short_rejects_invalid_header() {
+        let mut buf = ByteBuf::new(vec![0xff, 0, 0]);
+        assert!(buf.try_read_short().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough data")]
+    fn read_still_panics_on_truncated_data() {
+        ByteBuf::with_capacity(0).read_int();
+    }
+}
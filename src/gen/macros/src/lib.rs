@@ -24,11 +24,13 @@ pub fn enum_from_num(input: TokenStream) -> TokenStream {
     ];
     let expanded = quote! {
         #(
-            impl From<#tokens> for #ty_name {
-                fn from(value: #tokens) -> Self {
-                    (value as i32).into()
+            impl TryFrom<#tokens> for #ty_name {
+                type Error = <#ty_name as TryFrom<i32>>::Error;
+
+                fn try_from(value: #tokens) -> Result<Self, Self::Error> {
+                    (value as i32).try_into()
                 }
-            }            
+            }
         )*
     };
     
@@ -0,0 +1,434 @@
+use crate::bean::*;
+use crate::data::TableData;
+use crate::page::Page;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// 最多保留的撤销步数
+const MAX_HISTORY: usize = 200;
+
+/// 对表中一行的修改
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowEdit<T> {
+    Insert {
+        index: usize,
+        row: T,
+    },
+    Delete {
+        index: usize,
+        row: T,
+    },
+    Change {
+        index: usize,
+        fields: Vec<String>,
+        before: T,
+        after: T,
+    },
+}
+
+impl<T: Clone + TableRow> RowEdit<T> {
+    fn apply(&self, rows: &mut Vec<T>) {
+        match self {
+            RowEdit::Insert { index, row } => rows.insert(*index, row.clone()),
+            RowEdit::Delete { index, .. } => {
+                rows.remove(*index);
+            }
+            RowEdit::Change { index, after, .. } => rows[*index] = after.clone(),
+        }
+    }
+
+    fn revert(&self, rows: &mut Vec<T>) {
+        match self {
+            RowEdit::Insert { index, .. } => {
+                rows.remove(*index);
+            }
+            RowEdit::Delete { index, row } => rows.insert(*index, row.clone()),
+            RowEdit::Change { index, before, .. } => rows[*index] = before.clone(),
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            RowEdit::Insert { index, .. }
+            | RowEdit::Delete { index, .. }
+            | RowEdit::Change { index, .. } => *index,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            RowEdit::Insert { row, .. } => format!("#{} 添加", row.id()),
+            RowEdit::Delete { row, .. } => format!("#{} 删除", row.id()),
+            RowEdit::Change { after, fields, .. } => {
+                format!("#{} 修改 {}", after.id(), fields.join(", "))
+            }
+        }
+    }
+}
+
+/// 带有所属表的行修改
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableEdit {
+    Relics(RowEdit<Relics>),
+    Elements(RowEdit<Element>),
+    Enemies(RowEdit<Enemy>),
+    L10n(RowEdit<Localization>),
+    RaceAttributes(RowEdit<RaceAttribute>),
+}
+
+impl TableEdit {
+    fn apply(&self, table_data: &mut TableData) {
+        match self {
+            TableEdit::Relics(x) => x.apply(&mut table_data.relics),
+            TableEdit::Elements(x) => x.apply(&mut table_data.elements),
+            TableEdit::Enemies(x) => x.apply(&mut table_data.enemies),
+            TableEdit::L10n(x) => x.apply(&mut table_data.l10n),
+            TableEdit::RaceAttributes(x) => x.apply(&mut table_data.race_attributes),
+        }
+    }
+
+    fn revert(&self, table_data: &mut TableData) {
+        match self {
+            TableEdit::Relics(x) => x.revert(&mut table_data.relics),
+            TableEdit::Elements(x) => x.revert(&mut table_data.elements),
+            TableEdit::Enemies(x) => x.revert(&mut table_data.enemies),
+            TableEdit::L10n(x) => x.revert(&mut table_data.l10n),
+            TableEdit::RaceAttributes(x) => x.revert(&mut table_data.race_attributes),
+        }
+    }
+
+    /// 修改所在的页面和行
+    pub fn location(&self) -> (Page, usize) {
+        match self {
+            TableEdit::Relics(x) => (Page::Relic, x.index()),
+            TableEdit::Elements(x) => (Page::Element, x.index()),
+            TableEdit::Enemies(x) => (Page::Enemy, x.index()),
+            TableEdit::L10n(x) => (Page::L10n, x.index()),
+            TableEdit::RaceAttributes(x) => (Page::RaceAttribute, x.index()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let text = match self {
+            TableEdit::Relics(x) => x.describe(),
+            TableEdit::Elements(x) => x.describe(),
+            TableEdit::Enemies(x) => x.describe(),
+            TableEdit::L10n(x) => x.describe(),
+            TableEdit::RaceAttributes(x) => x.describe(),
+        };
+        format!("{} {}", self.location().0.get_info().title, text)
+    }
+}
+
+/// 一次可撤销的操作，包含多行时为批量修改
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditCommand {
+    pub edits: Vec<TableEdit>,
+}
+
+impl EditCommand {
+    pub fn is_bulk(&self) -> bool {
+        self.edits.len() > 1
+    }
+
+    pub fn describe(&self) -> String {
+        match self.edits.as_slice() {
+            [edit] => edit.describe(),
+            edits => format!("批量修改 {} 行: {}", edits.len(), edits[0].describe()),
+        }
+    }
+
+    fn apply(&self, table_data: &mut TableData) {
+        for edit in &self.edits {
+            edit.apply(table_data);
+        }
+    }
+
+    fn revert(&self, table_data: &mut TableData) {
+        for edit in self.edits.iter().rev() {
+            edit.revert(table_data);
+        }
+    }
+}
+
+/// 撤销/重做记录
+///
+/// 页面直接修改 `TableData`，这里保存上一次提交时的快照，
+/// 提交时与快照比较得到修改命令。
+#[derive(Default)]
+pub struct History {
+    snapshot: TableData,
+    undo: VecDeque<EditCommand>,
+    redo: Vec<EditCommand>,
+}
+
+impl History {
+    /// 加载或清除数据后清空记录
+    pub fn reset(&mut self, table_data: &TableData) {
+        self.snapshot = table_data.clone();
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// 把快照之后的修改记为一步操作，没有修改时返回false
+    pub fn commit(&mut self, table_data: &TableData) -> bool {
+        let edits = diff_tables(&self.snapshot, table_data);
+        if edits.is_empty() {
+            return false;
+        }
+        let command = EditCommand { edits };
+        command.apply(&mut self.snapshot);
+        self.undo.push_back(command);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+        true
+    }
+
+    pub fn can_undo(&self, table_data: &TableData) -> bool {
+        !self.undo.is_empty() || self.has_pending(table_data)
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn has_pending(&self, table_data: &TableData) -> bool {
+        !diff_tables(&self.snapshot, table_data).is_empty()
+    }
+
+    /// 撤销一步，返回被修改的页面和行
+    pub fn undo(&mut self, table_data: &mut TableData) -> Option<(Page, usize)> {
+        self.commit(table_data);
+        let command = self.undo.pop_back()?;
+        command.revert(table_data);
+        command.revert(&mut self.snapshot);
+        let location = command.edits[0].location();
+        self.redo.push(command);
+        Some(location)
+    }
+
+    /// 重做一步，返回被修改的页面和行
+    pub fn redo(&mut self, table_data: &mut TableData) -> Option<(Page, usize)> {
+        if self.commit(table_data) {
+            // 有新的修改时重做记录已失效
+            return None;
+        }
+        let command = self.redo.pop()?;
+        command.apply(table_data);
+        command.apply(&mut self.snapshot);
+        let location = command.edits[0].location();
+        self.undo.push_back(command);
+        Some(location)
+    }
+
+    /// 撤销或重做到第 `position` 步之后的状态
+    pub fn go_to(&mut self, table_data: &mut TableData, position: usize) {
+        self.commit(table_data);
+        while self.undo.len() > position && self.undo(table_data).is_some() {}
+        while self.undo.len() < position && self.redo(table_data).is_some() {}
+    }
+
+    /// 已执行的操作，从早到晚
+    pub fn done(&self) -> impl ExactSizeIterator<Item = &EditCommand> {
+        self.undo.iter()
+    }
+
+    /// 可重做的操作，从近到远
+    pub fn undone(&self) -> impl Iterator<Item = &EditCommand> {
+        self.redo.iter().rev()
+    }
+}
+
+/// 比较所有表，得到从 `old` 变为 `new` 的修改
+pub fn diff_tables(old: &TableData, new: &TableData) -> Vec<TableEdit> {
+    let mut edits = Vec::new();
+    edits.extend(
+        diff_rows(&old.relics, &new.relics)
+            .into_iter()
+            .map(TableEdit::Relics),
+    );
+    edits.extend(
+        diff_rows(&old.elements, &new.elements)
+            .into_iter()
+            .map(TableEdit::Elements),
+    );
+    edits.extend(
+        diff_rows(&old.enemies, &new.enemies)
+            .into_iter()
+            .map(TableEdit::Enemies),
+    );
+    edits.extend(
+        diff_rows(&old.l10n, &new.l10n)
+            .into_iter()
+            .map(TableEdit::L10n),
+    );
+    edits.extend(
+        diff_rows(&old.race_attributes, &new.race_attributes)
+            .into_iter()
+            .map(TableEdit::RaceAttributes),
+    );
+    edits
+}
+
+/// 去掉相同的首尾后，中间部分按下标逐行比较，多出的行记为添加或删除
+///
+/// 返回的修改按顺序应用即可从 `old` 得到 `new`。
+fn diff_rows<T: Clone + SameRow + Serialize>(old: &[T], new: &[T]) -> Vec<RowEdit<T>> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(a, b)| a.same_row(b))
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a.same_row(b))
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut edits = Vec::new();
+    for (i, (before, after)) in old_mid.iter().zip(new_mid).enumerate() {
+        if !before.same_row(after) {
+            edits.push(RowEdit::Change {
+                index: prefix + i,
+                fields: changed_fields(before, after),
+                before: before.clone(),
+                after: after.clone(),
+            });
+        }
+    }
+    let common = old_mid.len().min(new_mid.len());
+    for (i, row) in new_mid.iter().enumerate().skip(common) {
+        edits.push(RowEdit::Insert {
+            index: prefix + i,
+            row: row.clone(),
+        });
+    }
+    for (i, row) in old_mid.iter().enumerate().skip(common).rev() {
+        edits.push(RowEdit::Delete {
+            index: prefix + i,
+            row: row.clone(),
+        });
+    }
+    edits
+}
+
+/// 按json字段名列出修改过的字段
+fn changed_fields<T: Serialize>(before: &T, after: &T) -> Vec<String> {
+    match (serde_json::to_value(before), serde_json::to_value(after)) {
+        (Ok(serde_json::Value::Object(before)), Ok(serde_json::Value::Object(after))) => before
+            .iter()
+            .filter(|(key, value)| after.get(*key) != Some(*value))
+            .map(|(key, _)| key.clone())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relic(id: i32, rare: i32) -> Relics {
+        Relics {
+            id,
+            rare,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_restore_every_step() {
+        let mut table_data = TableData {
+            relics: vec![relic(1, 0), relic(2, 0), relic(3, 0)],
+            ..Default::default()
+        };
+        let mut history = History::default();
+        history.reset(&table_data);
+        let states = {
+            let mut states = vec![table_data.clone()];
+            // 中间插入
+            table_data.relics.insert(1, relic(4, 0));
+            assert!(history.commit(&table_data));
+            states.push(table_data.clone());
+            // 修改字段
+            table_data.relics[0].rare = 3;
+            assert!(history.commit(&table_data));
+            states.push(table_data.clone());
+            // 删除并跨表修改
+            table_data.relics.remove(2);
+            table_data.l10n.push(Localization {
+                id: 7,
+                ..Default::default()
+            });
+            assert!(history.commit(&table_data));
+            states.push(table_data.clone());
+            states
+        };
+        assert!(!history.commit(&table_data));
+
+        let commands: Vec<_> = history.done().cloned().collect();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[1].describe(), "遗物 #1 修改 Rare");
+        assert!(commands[2].is_bulk());
+
+        for state in states.iter().rev().skip(1) {
+            assert!(history.undo(&mut table_data).is_some());
+            assert_eq!(diff_tables(state, &table_data), vec![]);
+        }
+        assert!(history.undo(&mut table_data).is_none());
+
+        history.go_to(&mut table_data, 3);
+        assert_eq!(diff_tables(&states[3], &table_data), vec![]);
+        history.go_to(&mut table_data, 1);
+        assert_eq!(diff_tables(&states[1], &table_data), vec![]);
+    }
+
+    #[test]
+    fn new_edit_discards_redo() {
+        let mut table_data = TableData::default();
+        let mut history = History::default();
+        history.reset(&table_data);
+        table_data.enemies.push(Enemy::default());
+        history.undo(&mut table_data);
+        assert!(table_data.enemies.is_empty());
+        assert!(history.can_redo());
+
+        table_data.l10n.push(Localization::default());
+        assert_eq!(history.redo(&mut table_data), None);
+        assert!(!history.can_redo());
+        assert_eq!(table_data.l10n.len(), 1);
+        assert!(table_data.enemies.is_empty());
+    }
+
+    #[test]
+    fn element_field_edits_are_recorded() {
+        let mut table_data = TableData {
+            elements: vec![Element {
+                id: 5,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let saved = table_data.clone();
+        let mut history = History::default();
+        history.reset(&table_data);
+
+        // Element 按 id 判等，但修改其他字段仍然要记录
+        table_data.elements[0].icon = "cat".to_string();
+        assert_eq!(table_data.elements, saved.elements);
+        assert_eq!(table_data.dirty_tables(&saved), vec![Page::Element]);
+        assert!(history.commit(&table_data));
+        assert_eq!(
+            history.done().last().unwrap().describe(),
+            "单位 #5 修改 Icon"
+        );
+
+        history.undo(&mut table_data);
+        assert!(table_data.elements[0].icon.is_empty());
+    }
+}
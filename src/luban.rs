@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use crate::data::write_atomic;
 use cfg::prelude::*;
 use luban_lib::ByteBuf;
 
@@ -7,14 +8,26 @@ use luban_lib::ByteBuf;
 /// # 参数
 /// * `base_path` - 配置文件的基础路径
 /// ```
-pub fn load_tables(base_path: &str) -> Tables {
+pub fn load_tables(base_path: &str) -> Result<Tables, LubanError> {
     Tables::new(|name| {
         let path = PathBuf::from(format!("{}/{}.bytes", base_path, name));
-        Ok(ByteBuf::new(std::fs::read(path).unwrap()))
-    }).expect("Failed to load tables")
+        std::fs::read(&path)
+            .map(ByteBuf::new)
+            .map_err(|e| LubanError::Loader(format!("{}: {}", path.display(), e)))
+    })
 }
 
 /// 使用默认路径加载配置表的函数
-pub fn load_tables_default() -> Tables {
+pub fn load_tables_default() -> Result<Tables, LubanError> {
     load_tables("../GenerateDatas/bytes")
-}
\ No newline at end of file
+}
+/// 把配置表写回.bytes文件，与`load_tables`读取的文件名一致
+pub fn save_tables(base_path: &Path, tables: &Tables) -> Result<(), LubanError> {
+    std::fs::create_dir_all(base_path)
+        .map_err(|e| LubanError::Loader(format!("{}: {}", base_path.display(), e)))?;
+    tables.save(|name, buf| {
+        let path = base_path.join(format!("{}.bytes", name));
+        write_atomic(&path, &buf.copy_data())
+            .map_err(|e| LubanError::Loader(format!("{}: {}", path.display(), e)))
+    })
+}
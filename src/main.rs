@@ -1,10 +1,23 @@
+mod assets;
+mod backup;
 mod bean;
+mod cli;
 mod data;
 mod font;
+mod history;
 mod page;
 mod page_render;
+mod preflight;
+mod published;
+mod sound;
+#[cfg(test)]
+mod testutil;
+mod thumbnail;
+mod upload;
 mod uploadscreen;
+mod validation;
 mod welcome_screen;
+mod workshop;
 mod luban;
 
 use data::*;
@@ -47,6 +60,13 @@ fn main() -> eframe::Result {
     logger_builder.filter_level(log::LevelFilter::Error);
 
     logger_builder.build();
+
+    // 带子命令时以命令行模式运行，不创建窗口
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     let mut native_options = eframe::NativeOptions::default();
     native_options.viewport = ViewportBuilder::default()
         .with_inner_size([1280.0, 720.0])
@@ -56,7 +76,7 @@ fn main() -> eframe::Result {
         APP_NAME,
         native_options,
         Box::new(|cc| {
-            font::setup_custom_fonts(&cc.egui_ctx);
+            egui_extras::install_image_loaders(&cc.egui_ctx);
             let app_data = if let Some(storage) = cc.storage
                 && let Some(app_data) = get_value::<AppData>(storage, APP_DATA_KEY)
             {
@@ -67,6 +87,13 @@ fn main() -> eframe::Result {
 
             let mut app_state = AppState::default();
             app_state.ctx = cc.egui_ctx.clone();
+            let font_status =
+                font::setup_custom_fonts(&cc.egui_ctx, app_data.font_path.as_deref());
+            if let Err(e) = &font_status {
+                log::warn!("{}", e);
+                app_state.messages.push_back(e.clone());
+            }
+            app_state.settings_window.font_status = Some(font_status);
 
             Ok(Box::new(MyApp {
                 app_data,
@@ -94,6 +121,8 @@ struct AppData {
     sidebar_collapsed: bool,
     workspaces: Vec<String>,
     current_workspace: usize,
+    /// 用户选择的字体文件，为空时自动查找
+    font_path: Option<String>,
 }
 
 #[derive(Default)]
@@ -105,7 +134,29 @@ struct AppState {
     table_data: TableData,
     show_confirmation_dialog: bool,
     allowed_to_close: bool,
+    /// 等待上传结束后再关闭
+    close_after_upload: bool,
     scroll_to_row: Option<usize>,
+    history: history::History,
+    show_history: bool,
+    saved_data: TableData,
+    dirty_tables: Vec<Page>,
+    backup_window: backup::BackupWindowState,
+    validation_window: validation::ValidationWindowState,
+    images: assets::ImageIndex,
+    asset_window: assets::AssetWindowState,
+    settings_window: font::SettingsWindowState,
+    sounds: sound::SoundRegistry,
+    sound_window: sound::SoundWindowState,
+    published_window: published::PublishedWindowState,
+    /// 所有界面共用的创意工坊连接
+    workshop: workshop::WorkshopSession,
+
+    relics_page: page_render::relicspage::RelicsPageState,
+    element_page: page_render::elementpage::ElementPageState,
+    enemy_page: page_render::enemypage::EnemyPageState,
+    l10n_page: page_render::l10n::L10nPageState,
+    race_attribute_page: page_render::raceattributepage::RaceAttributePageState,
 
     mod_creation_data: ModCreationData, // 添加MOD对话框相关状态
     show_create_dialog: bool,           // 显示创建MOD的对话框
@@ -122,24 +173,57 @@ struct ModCreationData {
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.handle_undo_shortcuts(ctx);
+        let workspace = self.get_current_workspace().map(str::to_string);
+        self.app_state.images.sync(workspace.as_deref());
+        if let Some(e) = self.app_state.sounds.sync(workspace.as_deref()) {
+            self.app_state.messages.push_back(e);
+        }
+        self.app_state.dirty_tables = self
+            .app_state
+            .table_data
+            .dirty_tables(&self.app_state.saved_data);
         self.render_sidebar(ctx);
         self.render_create_mod_dialog();
+        self.render_history_window(ctx);
+        backup::render_backup_window(self, ctx);
+        validation::render_validation_window(self, ctx);
+        assets::render_asset_window(self, ctx);
+        sound::render_sound_window(self, ctx);
+        font::render_settings_window(self, ctx);
+        published::render_published_window(self, ctx);
 
         // 渲染上传界面弹窗
         if self.show_state == ShowState::Upload {
-            if self.upload_screen.ui(ctx) {
+            if self.upload_screen.ui(ctx, &mut self.app_state.workshop) {
                 self.show_state = ShowState::None;
             }
+        } else {
+            self.upload_screen.poll(ctx);
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             self.render_main_content(ui);
         });
 
+        // 输入结束后把本帧之前的修改记为一步操作，连续输入只记一次
+        if !ctx.wants_keyboard_input() && !ctx.input(|i| i.pointer.any_down()) {
+            self.app_state.history.commit(&self.app_state.table_data);
+        }
+
+        // 上传结束后继续关闭
+        if self.app_state.close_after_upload && !self.upload_screen.is_uploading() {
+            self.app_state.close_after_upload = false;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
         // 处理关闭事件
         if ctx.input(|i| i.viewport().close_requested()) {
-            if self.app_state.allowed_to_close {
-                // 允许关闭，不需要做任何事
+            let uploading = self.upload_screen.is_uploading();
+            if (self.app_state.allowed_to_close || self.app_state.dirty_tables.is_empty())
+                && !uploading
+            {
+                // 允许关闭或没有未保存的修改和上传，不需要做任何事
             } else {
                 // 取消关闭并显示确认对话框
                 ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
@@ -147,8 +231,36 @@ impl eframe::App for MyApp {
             }
         }
 
-        // 显示确认对话框
-        if self.app_state.show_confirmation_dialog {
+        // 上传中关闭时先询问是取消还是等待上传
+        if self.app_state.show_confirmation_dialog && self.upload_screen.is_uploading() {
+            let modal = egui::Modal::new("exit_upload_confirmation".into()).show(ctx, |ui| {
+                ui.set_width(300.0);
+                ui.heading("正在上传到创意工坊");
+                ui.add_space(8.0);
+                ui.label("现在退出会中断上传，可以取消上传或等待上传完成后再退出");
+                ui.add_space(24.0);
+                let mut cancel = false;
+                let mut wait = false;
+                Sides::default().show(
+                    ui,
+                    |ui| cancel = ui.button("取消上传并退出").clicked(),
+                    |ui| wait = ui.button("等待上传完成").clicked(),
+                );
+                if cancel {
+                    self.upload_screen.cancel_upload();
+                } else if wait {
+                    // 显示上传窗口以便查看进度
+                    self.show_state = ShowState::Upload;
+                }
+                if cancel || wait {
+                    self.app_state.show_confirmation_dialog = false;
+                    self.app_state.close_after_upload = true;
+                }
+            });
+            if modal.should_close() {
+                self.app_state.show_confirmation_dialog = false;
+            }
+        } else if self.app_state.show_confirmation_dialog {
             let modal = egui::Modal::new("exit_confirmation".into()).show(ctx, |ui| {
                 ui.set_width(300.0);
 
@@ -163,7 +275,15 @@ impl eframe::App for MyApp {
                     })
                 });
 
-                ui.add_space(32.0);
+                ui.add_space(8.0);
+                let titles: Vec<&str> = self
+                    .app_state
+                    .dirty_tables
+                    .iter()
+                    .map(|x| x.get_info().title)
+                    .collect();
+                ui.label(format!("以下表有未保存的修改: {}", titles.join("、")));
+                ui.add_space(24.0);
 
                 let mut show_confirmation_dialog = self.app_state.show_confirmation_dialog;
                 let mut allowed_to_close = self.app_state.allowed_to_close;
@@ -182,8 +302,16 @@ impl eframe::App for MyApp {
                         if ui.button("保存并退出").clicked() {
                             save_data(self);
                             self.app_state.show_confirmation_dialog = false;
-                            self.app_state.allowed_to_close = true;
-                            ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+                            // 保存失败时留在编辑器里，错误信息会弹出
+                            let saved = self
+                                .app_state
+                                .table_data
+                                .dirty_tables(&self.app_state.saved_data)
+                                .is_empty();
+                            if saved {
+                                self.app_state.allowed_to_close = true;
+                                ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+                            }
                         }
                     },
                 );
@@ -205,6 +333,11 @@ impl eframe::App for MyApp {
     fn save(&mut self, _storage: &mut dyn Storage) {
         set_value(_storage, APP_DATA_KEY, &self.app_data);
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // 停止Steam回调线程
+        self.app_state.workshop.shutdown();
+    }
 }
 
 impl MyApp {
@@ -227,10 +360,15 @@ impl MyApp {
                     ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
                         for page in SIDEBAR_PAGES {
                             let info = page.get_info();
+                            let title = if self.app_state.dirty_tables.contains(page) {
+                                format!("{} ●", info.title)
+                            } else {
+                                format!("{}", info.title)
+                            };
                             if ui
                                 .selectable_label(
                                     self.app_data.selected_page == Some(page.clone()),
-                                    format!("{}", info.title),
+                                    title,
                                 )
                                 .clicked()
                             {
@@ -266,14 +404,12 @@ impl MyApp {
                             if has_editing_mod {
                                 // 在这里添加按钮2的点击逻辑
                                 self.show_state = ShowState::Upload;
-                                // 初始化上传界面数据
-                                self.upload_screen.reset();
-                                if let Some(workspace_path) = self.get_current_workspace() {
-                                    self.upload_screen.folder_path = workspace_path.to_string();
+                                // 上传还没结束时保留界面，继续显示进度
+                                if !self.upload_screen.is_uploading() {
+                                    self.init_upload_screen();
                                 }
-                                self.upload_screen.mod_name = "当前MOD名称".to_string(); // 需要从当前编辑的MOD获取
-                                self.upload_screen.mod_description = "当前MOD描述".to_string();
-                            // 需要从当前编辑的MOD获取
+                                // 只在第一次打开或上次连接失败时初始化
+                                self.upload_screen.connect(&mut self.app_state.workshop);
                             } else {
                                 // 显示提示信息
                                 self.app_state
@@ -283,6 +419,13 @@ impl MyApp {
                                     self.app_state.messages.pop_front();
                             }
                         }
+
+                        if ui
+                            .add_sized([150.0, 30.0], egui::Button::new("我发布的物品"))
+                            .clicked()
+                        {
+                            published::open_published_window(self);
+                        }
                         ui.add_space(10.0); // 添加一些间距
                     });
                 });
@@ -329,6 +472,26 @@ impl MyApp {
 
             self.render_workspace_state(ui);
 
+            ui.separator();
+
+            let can_undo = self.app_state.history.can_undo(&self.app_state.table_data);
+            let can_redo = self.app_state.history.can_redo();
+            if ui
+                .add_enabled(can_undo, egui::Button::new("↶ 撤销"))
+                .on_hover_text("Ctrl+Z")
+                .clicked()
+            {
+                self.undo();
+            }
+            if ui
+                .add_enabled(can_redo, egui::Button::new("↷ 重做"))
+                .on_hover_text("Ctrl+Y")
+                .clicked()
+            {
+                self.redo();
+            }
+            ui.toggle_value(&mut self.app_state.show_history, "历史");
+
             ui.with_layout(egui::Layout::right_to_left(Align::Center), |ui| {
                 if ui.button("加载数据").clicked() {
                     read_data(self);
@@ -339,10 +502,112 @@ impl MyApp {
                 if ui.button("清除数据").clicked() {
                     clear_data(self);
                 }
+                if ui.button("恢复备份").clicked() {
+                    backup::open_backup_window(self);
+                }
+                if ui.button("校验数据").clicked() {
+                    validation::open_validation_window(self);
+                }
+                if ui.button("图片资源").clicked() {
+                    self.app_state.images.refresh();
+                    self.app_state.asset_window.open = true;
+                }
+                if ui.button("音效资源").clicked() {
+                    if let Err(e) = self.app_state.sounds.refresh() {
+                        self.app_state.messages.push_back(e);
+                    }
+                    self.app_state.sound_window.open = true;
+                }
+                if ui.button("设置").clicked() {
+                    font::open_settings_window(self);
+                }
             });
         });
     }
 
+    fn handle_undo_shortcuts(&mut self, ctx: &egui::Context) {
+        // 文本框有自己的撤销
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let redo = ctx.input_mut(|i| {
+            i.consume_shortcut(&KeyboardShortcut::new(
+                Modifiers::COMMAND | Modifiers::SHIFT,
+                Key::Z,
+            )) || i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Y))
+        });
+        if redo {
+            self.redo();
+        } else if ctx
+            .input_mut(|i| i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Z)))
+        {
+            self.undo();
+        }
+    }
+
+    fn undo(&mut self) {
+        let location = self.app_state.history.undo(&mut self.app_state.table_data);
+        self.jump_to(location);
+    }
+
+    fn redo(&mut self) {
+        let location = self.app_state.history.redo(&mut self.app_state.table_data);
+        self.jump_to(location);
+    }
+
+    /// 跳到被撤销或重做的行
+    fn jump_to(&mut self, location: Option<(Page, usize)>) {
+        if let Some((page, index)) = location {
+            self.app_data.selected_page = Some(page);
+            self.app_state.scroll_to_row = Some(index);
+        }
+    }
+
+    fn render_history_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.app_state.show_history;
+        let mut go_to = None;
+        egui::Window::new("编辑历史")
+            .open(&mut open)
+            .default_width(320.0)
+            .default_height(400.0)
+            .show(ctx, |ui| {
+                let history = &self.app_state.history;
+                let done = history.done().len();
+                ScrollArea::vertical().show(ui, |ui| {
+                    if ui.selectable_label(done == 0, "初始状态").clicked() {
+                        go_to = Some(0);
+                    }
+                    let entries = history
+                        .done()
+                        .map(|x| (x, false))
+                        .chain(history.undone().map(|x| (x, true)));
+                    for (i, (command, undone)) in entries.enumerate() {
+                        let mut text = RichText::new(command.describe());
+                        if undone {
+                            text = text.weak();
+                        }
+                        let mut response = ui.selectable_label(i + 1 == done, text);
+                        if command.is_bulk() {
+                            response = response.on_hover_ui(|ui| {
+                                for edit in &command.edits {
+                                    ui.label(edit.describe());
+                                }
+                            });
+                        }
+                        if response.clicked() {
+                            go_to = Some(i + 1);
+                        }
+                    }
+                });
+            });
+        self.app_state.show_history = open;
+        if let Some(position) = go_to {
+            self.app_state
+                .history
+                .go_to(&mut self.app_state.table_data, position);
+        }
+    }
+
     fn render_message(&mut self) {
         if self.app_state.current_message.is_none() {
             self.app_state.current_message = self.app_state.messages.pop_front();
@@ -463,6 +728,19 @@ impl MyApp {
             .map(|s| s.as_str())
     }
 
+    /// 初始化上传界面数据
+    fn init_upload_screen(&mut self) {
+        self.upload_screen.reset();
+        if let Some(workspace_path) = self.get_current_workspace() {
+            self.upload_screen.folder_path = workspace_path.to_string();
+        }
+        self.upload_screen.mod_name = "当前MOD名称".to_string(); // 需要从当前编辑的MOD获取
+        self.upload_screen.mod_description = "当前MOD描述".to_string();
+        // 已有moddata.json时使用其中的名称、描述和物品id
+        self.upload_screen.load_mod_data();
+        self.upload_screen.tags = uploadscreen::table_tags(&self.app_state.table_data);
+    }
+
     pub fn add_mod(&mut self, _ui: &mut egui::Ui) {
         // 重置表单数据
         self.app_state.mod_creation_data = ModCreationData {
@@ -578,6 +856,7 @@ impl MyApp {
                                                 name: mod_name.clone(),
                                                 desc: mod_description.clone(),
                                                 version: "1.0.0".to_string(),
+                                                mod_id: None,
                                             };
 
                                             let mod_data_path = mod_root_path.join("moddata.json");
@@ -32,19 +32,19 @@ impl Page {
         ui.separator();
         match self {
             Page::Relic => {
-                ui.label("你好");
+                relicspage::render(ui, app);
             }
             Page::Element => {
-                ui.label("你好");
+                elementpage::render(ui, app);
             }
             Page::Enemy => {
-                ui.label("你好");
+                enemypage::render(ui, app);
             }
             Page::L10n => {
-                ui.label("你好");
+                l10n::render(ui, app);
             }
             Page::RaceAttribute => {
-                ui.label("你好");
+                raceattributepage::render(ui, app);
             }
         }
     }
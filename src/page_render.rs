@@ -1,6 +1,6 @@
 pub mod l10n;
-mod elementpage;
-mod enemypage;
-mod relicspage;
-mod libpage;
-mod raceattributepage;
+pub mod elementpage;
+pub mod enemypage;
+pub mod relicspage;
+pub mod libpage;
+pub mod raceattributepage;
@@ -0,0 +1,299 @@
+use super::libpage::*;
+use crate::assets::ImageIndex;
+use crate::bean::{Element, ElementAttribute, ElementTriggerValue};
+use crate::sound::SoundRegistry;
+use crate::{Align, CentralPanel, Layout, MyApp, ScrollArea, Sense, SidePanel, egui};
+use egui_extras::{Column, TableBuilder};
+use std::cmp::Ordering;
+
+#[derive(Default)]
+pub struct ElementPageState {
+    selected: Option<usize>,
+    sort: SortState,
+}
+
+const COLUMNS: [&str; 8] = [
+    "Id",
+    "名称",
+    "图标",
+    "稀有度",
+    "种族",
+    "其他种族",
+    "启用",
+    "锁定",
+];
+
+fn compare(a: &Element, b: &Element, column: usize) -> Ordering {
+    match column {
+        1 => a.name_id.cmp(&b.name_id),
+        2 => a.icon.cmp(&b.icon),
+        3 => a.rare.cmp(&b.rare),
+        4 => (a.race_type as i32).cmp(&(b.race_type as i32)),
+        5 => (a.other_race as i32).cmp(&(b.other_race as i32)),
+        6 => a.enable.cmp(&b.enable),
+        7 => a.lock.cmp(&b.lock),
+        _ => Ordering::Equal,
+    }
+    .then(a.id.cmp(&b.id))
+}
+
+pub fn render(ui: &mut egui::Ui, app: &mut MyApp) {
+    let state = &mut app.app_state.element_page;
+    let table_data = &mut app.app_state.table_data;
+    let rows = &mut table_data.elements;
+
+    let mut scroll_to = app.app_state.scroll_to_row.take();
+    if scroll_to.is_some() {
+        state.selected = scroll_to;
+    }
+    state.selected = state.selected.filter(|&i| i < rows.len());
+
+    if let Some(action) = row_toolbar(ui, state.selected.is_some()) {
+        state.selected = apply_row_action(
+            rows,
+            state.selected,
+            action,
+            |id| Element {
+                id,
+                ..Default::default()
+            },
+            |row, id| row.id = id,
+        );
+        scroll_to = state.selected;
+    }
+    ui.separator();
+
+    SidePanel::right("element_detail")
+        .resizable(true)
+        .default_width(420.0)
+        .show_inside(ui, |ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                match state.selected.and_then(|i| rows.get_mut(i)) {
+                    Some(row) => {
+                        element_form(ui, row, &app.app_state.images, &app.app_state.sounds);
+                    }
+                    None => {
+                        ui.label("请在左侧选择一个单位");
+                    }
+                }
+            });
+        });
+
+    CentralPanel::default().show_inside(ui, |ui| {
+        let order = state.sort.sorted_indices(rows, compare);
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .sense(Sense::click())
+            .cell_layout(Layout::left_to_right(Align::Center))
+            .columns(Column::auto().at_least(50.0), COLUMNS.len() - 1)
+            .column(Column::remainder());
+        if let Some(target) = scroll_to.and_then(|i| order.iter().position(|&x| x == i)) {
+            table = table.scroll_to_row(target, Some(Align::Center));
+        }
+        table
+            .header(20.0, |mut header| {
+                for (i, title) in COLUMNS.iter().enumerate() {
+                    header.col(|ui| sort_header(ui, &mut state.sort, i, title));
+                }
+            })
+            .body(|body| {
+                body.rows(20.0, order.len(), |mut row| {
+                    let index = order[row.index()];
+                    let element = &rows[index];
+                    row.set_selected(state.selected == Some(index));
+                    row.col(|ui| {
+                        ui.label(element.id.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(l10n_name(&table_data.l10n, element.name_id));
+                    });
+                    row.col(|ui| {
+                        icon_label(ui, &element.icon, &app.app_state.images);
+                    });
+                    row.col(|ui| {
+                        ui.label(rare_name(element.rare));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:?}", element.race_type));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:?}", element.other_race));
+                    });
+                    row.col(|ui| {
+                        ui.label(if element.enable != 0 { "✔" } else { "" });
+                    });
+                    row.col(|ui| {
+                        ui.label(if element.lock != 0 { "🔒" } else { "" });
+                    });
+                    if row.response().clicked() {
+                        state.selected = Some(index);
+                    }
+                });
+            });
+    });
+}
+
+fn element_form(
+    ui: &mut egui::Ui,
+    row: &mut Element,
+    images: &ImageIndex,
+    sounds: &SoundRegistry,
+) -> bool {
+    let mut changed = false;
+    egui::Grid::new("element_form")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            field_label(ui, "Id", "这是id");
+            changed |= int_edit(ui, &mut row.id);
+            ui.end_row();
+
+            field_label(ui, "Lock", "是否锁定");
+            changed |= flag_edit(ui, &mut row.lock);
+            ui.end_row();
+
+            field_label(ui, "Enable", "是否启用");
+            changed |= flag_edit(ui, &mut row.enable);
+            ui.end_row();
+
+            field_label(ui, "Role", "专属角色");
+            changed |= int_edit(ui, &mut row.role);
+            ui.end_row();
+
+            field_label(ui, "NameId", "名称ID");
+            changed |= int_edit(ui, &mut row.name_id);
+            ui.end_row();
+
+            field_label(ui, "DescId", "描述ID");
+            changed |= int_edit(ui, &mut row.desc_id);
+            ui.end_row();
+
+            field_label(ui, "Desctip", "特殊描述类型");
+            changed |= etip_list_edit(ui, "element_desctip", &mut row.desctip);
+            ui.end_row();
+
+            field_label(ui, "DescAttribute", "描述使用属性");
+            changed |= int_list_edit(ui, &mut row.desc_attribute);
+            ui.end_row();
+
+            field_label(ui, "Icon", "全身像");
+            changed |= icon_edit(ui, "element_icon", &mut row.icon, images);
+            ui.end_row();
+
+            field_label(ui, "Rare", "稀有度");
+            changed |= rare_edit(ui, "element_rare", &mut row.rare);
+            ui.end_row();
+
+            field_label(ui, "RaceType", "种族");
+            changed |= race_edit(ui, "element_race_type", &mut row.race_type);
+            ui.end_row();
+
+            field_label(ui, "OtherRace", "其他种族");
+            changed |= race_edit(ui, "element_other_race", &mut row.other_race);
+            ui.end_row();
+
+            field_label(ui, "TriggerType", "触发器");
+            changed |= int_edit(ui, &mut row.trigger_type);
+            ui.end_row();
+
+            field_label(ui, "TriggerParam", "触发参数");
+            changed |= int_list_edit(ui, &mut row.trigger_param);
+            ui.end_row();
+
+            field_label(ui, "EventTip", "事件响应语言ID");
+            changed |= int_edit(ui, &mut row.event_tip);
+            ui.end_row();
+
+            field_label(ui, "TriggerAction", "触发行为");
+            changed |= int_edit(ui, &mut row.trigger_action);
+            ui.end_row();
+
+            field_label(ui, "OtherValue", "其他参数");
+            changed |= int_list_edit(ui, &mut row.other_value);
+            ui.end_row();
+
+            field_label(ui, "AttackSound", "攻击音效");
+            changed |= sound_edit(ui, "element_attack_sound", &mut row.attack_sound, sounds);
+            ui.end_row();
+
+            field_label(ui, "SelectSound", "选中音效");
+            changed |= sound_edit(ui, "element_select_sound", &mut row.select_sound, sounds);
+            ui.end_row();
+
+            field_label(ui, "AttackParticle", "攻击特效");
+            changed |= int_edit(ui, &mut row.attack_particle);
+            ui.end_row();
+        });
+
+    ui.separator();
+    ui.strong("初始属性 Attribute");
+    changed |= attribute_table(ui, &mut row.attribute);
+
+    ui.separator();
+    ui.strong("触发行为时数值 TriggerValue");
+    changed |= trigger_value_table(ui, &mut row.trigger_value);
+    changed
+}
+
+/// 初始属性子表
+fn attribute_table(ui: &mut egui::Ui, attributes: &mut Vec<ElementAttribute>) -> bool {
+    let mut changed = false;
+    let mut remove = None;
+    egui::Grid::new("element_attribute")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("属性Id");
+            ui.label("数值");
+            ui.label("");
+            ui.end_row();
+            for (i, attribute) in attributes.iter_mut().enumerate() {
+                changed |= int_edit(ui, &mut attribute.id);
+                changed |= int_edit(ui, &mut attribute.value);
+                if ui.small_button("×").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+    if let Some(i) = remove {
+        attributes.remove(i);
+        changed = true;
+    }
+    if ui.button("➕ 添加属性").clicked() {
+        attributes.push(ElementAttribute::default());
+        changed = true;
+    }
+    changed
+}
+
+/// 每级触发数值子表
+fn trigger_value_table(ui: &mut egui::Ui, levels: &mut Vec<ElementTriggerValue>) -> bool {
+    let mut changed = false;
+    let mut remove = None;
+    egui::Grid::new("element_trigger_value")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            for (i, level) in levels.iter_mut().enumerate() {
+                ui.label(format!("Lv{}", i + 1));
+                changed |= int_list_edit(ui, &mut level.value);
+                if ui.small_button("×").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+    if let Some(i) = remove {
+        levels.remove(i);
+        changed = true;
+    }
+    if ui.button("➕ 添加等级").clicked() {
+        // 新等级沿用上一级的数值，便于逐级调整
+        let level = levels.last().cloned().unwrap_or_default();
+        levels.push(level);
+        changed = true;
+    }
+    changed
+}
@@ -0,0 +1,381 @@
+use super::libpage::*;
+use crate::assets::ImageIndex;
+use crate::bean::Enemy;
+use crate::sound::SoundRegistry;
+use crate::{
+    Align, Align2, CentralPanel, Color32, FontId, MyApp, Pos2, Rect, RichText, ScrollArea, Sense,
+    SidePanel, Stroke, TopBottomPanel, Vec2, egui,
+};
+use std::collections::BTreeMap;
+
+const BOSS_COLOR: Color32 = Color32::from_rgb(230, 80, 80);
+const NORMAL_COLOR: Color32 = Color32::from_rgb(90, 160, 240);
+const ENDLESS_COLOR: Color32 = Color32::from_rgb(240, 160, 60);
+
+#[derive(Default)]
+pub struct EnemyPageState {
+    selected: Option<usize>,
+}
+
+pub fn render(ui: &mut egui::Ui, app: &mut MyApp) {
+    let state = &mut app.app_state.enemy_page;
+    let table_data = &mut app.app_state.table_data;
+    let rows = &mut table_data.enemies;
+
+    let mut scroll_to = app.app_state.scroll_to_row.take();
+    if scroll_to.is_some() {
+        state.selected = scroll_to;
+    }
+    state.selected = state.selected.filter(|&i| i < rows.len());
+
+    if let Some(action) = row_toolbar(ui, state.selected.is_some()) {
+        // 新敌人默认放在当前选中敌人所在的地图和关卡
+        let (map, level) = state
+            .selected
+            .map_or((0, 0), |i| (rows[i].map, rows[i].level));
+        state.selected = apply_row_action(
+            rows,
+            state.selected,
+            action,
+            |id| Enemy {
+                id,
+                map,
+                level,
+                ..Default::default()
+            },
+            |row, id| row.id = id,
+        );
+        scroll_to = state.selected;
+    }
+    ui.separator();
+
+    SidePanel::right("enemy_detail")
+        .resizable(true)
+        .default_width(380.0)
+        .show_inside(ui, |ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                match state.selected.and_then(|i| rows.get_mut(i)) {
+                    Some(row) => {
+                        enemy_form(ui, row, &app.app_state.images, &app.app_state.sounds);
+                    }
+                    None => {
+                        ui.label("请在左侧选择一个敌人");
+                    }
+                }
+            });
+        });
+
+    // 曲线显示选中敌人所在地图，未选中时显示第一个地图
+    let chart_map = state
+        .selected
+        .map(|i| rows[i].map)
+        .or_else(|| rows.iter().map(|x| x.map).min());
+    TopBottomPanel::bottom("enemy_chart")
+        .resizable(true)
+        .default_height(220.0)
+        .show_inside(ui, |ui| {
+            if let Some(map) = chart_map {
+                render_charts(ui, rows, map, state.selected);
+            }
+        });
+
+    CentralPanel::default().show_inside(ui, |ui| {
+        ScrollArea::vertical().show(ui, |ui| {
+            for (map, levels) in group_by_map_and_level(rows) {
+                let open = scroll_to.map(|i| rows[i].map == map);
+                egui::CollapsingHeader::new(format!("地图 {}", map))
+                    .id_salt(("enemy_map", map))
+                    .default_open(true)
+                    .open(open.filter(|x| *x))
+                    .show(ui, |ui| {
+                        for (level, indices) in levels {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.label(RichText::new(format!("关卡 {}", level)).weak());
+                                for index in indices {
+                                    let enemy = &rows[index];
+                                    let mut text =
+                                        RichText::new(l10n_name(&table_data.l10n, enemy.name_id));
+                                    if enemy.boss != 0 {
+                                        text = text.color(BOSS_COLOR).strong();
+                                    }
+                                    let response = ui
+                                        .selectable_label(state.selected == Some(index), text)
+                                        .on_hover_text(format!("Id: {}", enemy.id));
+                                    if scroll_to == Some(index) {
+                                        response.scroll_to_me(Some(Align::Center));
+                                    }
+                                    if response.clicked() {
+                                        state.selected = Some(index);
+                                    }
+                                }
+                            });
+                        }
+                    });
+            }
+        });
+    });
+}
+
+/// 按地图、关卡分组，组内按id排序
+fn group_by_map_and_level(rows: &[Enemy]) -> BTreeMap<i32, BTreeMap<i32, Vec<usize>>> {
+    let mut groups: BTreeMap<i32, BTreeMap<i32, Vec<usize>>> = BTreeMap::new();
+    for (i, enemy) in rows.iter().enumerate() {
+        groups
+            .entry(enemy.map)
+            .or_default()
+            .entry(enemy.level)
+            .or_default()
+            .push(i);
+    }
+    for levels in groups.values_mut() {
+        for indices in levels.values_mut() {
+            indices.sort_by_key(|&i| rows[i].id);
+        }
+    }
+    groups
+}
+
+fn enemy_form(
+    ui: &mut egui::Ui,
+    row: &mut Enemy,
+    images: &ImageIndex,
+    sounds: &SoundRegistry,
+) -> bool {
+    let mut changed = false;
+    egui::Grid::new("enemy_form")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            field_label(ui, "Id", "这是id");
+            changed |= int_edit(ui, &mut row.id);
+            ui.end_row();
+
+            field_label(ui, "Icon", "图标");
+            changed |= icon_edit(ui, "enemy_icon", &mut row.icon, images);
+            ui.end_row();
+
+            field_label(ui, "NameId", "多语言ID");
+            changed |= int_edit(ui, &mut row.name_id);
+            ui.end_row();
+
+            field_label(ui, "DescId", "描述多语言");
+            changed |= int_edit(ui, &mut row.desc_id);
+            ui.end_row();
+
+            field_label(ui, "Map", "地图");
+            changed |= int_edit(ui, &mut row.map);
+            ui.end_row();
+
+            field_label(ui, "Level", "关卡");
+            changed |= int_edit(ui, &mut row.level);
+            ui.end_row();
+
+            field_label(ui, "Boss", "BOSS类型");
+            changed |= int_edit(ui, &mut row.boss);
+            ui.end_row();
+
+            field_label(ui, "Hp", "血量");
+            changed |= int_edit(ui, &mut row.hp);
+            ui.end_row();
+
+            field_label(ui, "Attack", "攻击力");
+            changed |= int_edit(ui, &mut row.attack);
+            ui.end_row();
+
+            field_label(ui, "UpgradeAttack", "升级攻击力");
+            changed |= int_edit(ui, &mut row.upgrade_attack);
+            ui.end_row();
+
+            field_label(ui, "EndlessHp", "无尽模式血量");
+            changed |= int_edit(ui, &mut row.endless_hp);
+            ui.end_row();
+
+            field_label(ui, "EndlessAttack", "无尽模式攻击力");
+            changed |= int_edit(ui, &mut row.endless_attack);
+            ui.end_row();
+
+            field_label(ui, "EndlessUpgrade", "无尽模式升级攻击力");
+            changed |= int_edit(ui, &mut row.endless_upgrade);
+            ui.end_row();
+
+            field_label(ui, "Gold", "奖励金币");
+            changed |= int_edit(ui, &mut row.gold);
+            ui.end_row();
+
+            field_label(ui, "GoldMax", "最大奖励金币");
+            ui.horizontal(|ui| {
+                changed |= int_edit(ui, &mut row.gold_max);
+                if row.gold > row.gold_max {
+                    ui.colored_label(Color32::RED, "⚠ 奖励金币大于最大奖励金币");
+                }
+            });
+            ui.end_row();
+
+            field_label(ui, "AttackSound", "攻击音效");
+            changed |= sound_edit(ui, "enemy_attack_sound", &mut row.attack_sound, sounds);
+            ui.end_row();
+
+            field_label(ui, "ShowSound", "出场音效");
+            changed |= sound_edit(ui, "enemy_show_sound", &mut row.show_sound, sounds);
+            ui.end_row();
+        });
+    changed
+}
+
+/// 从敌人取出普通和无尽模式下的同一项数值
+type StatFn = fn(&Enemy) -> (i32, i32);
+
+/// 数值曲线上的一个点
+struct StatPoint {
+    level: i32,
+    normal: i32,
+    endless: i32,
+    selected: bool,
+    boss: bool,
+}
+
+fn render_charts(ui: &mut egui::Ui, rows: &[Enemy], map: i32, selected: Option<usize>) {
+    ui.horizontal(|ui| {
+        ui.strong(format!("地图 {} 数值曲线", map));
+        ui.colored_label(NORMAL_COLOR, "━ 普通");
+        ui.colored_label(ENDLESS_COLOR, "━ 无尽");
+    });
+
+    let stats: [(&str, StatFn); 3] = [
+        ("Hp", |x| (x.hp, x.endless_hp)),
+        ("Attack", |x| (x.attack, x.endless_attack)),
+        ("UpgradeAttack", |x| (x.upgrade_attack, x.endless_upgrade)),
+    ];
+    let size = Vec2::new(
+        (ui.available_width() - ui.spacing().item_spacing.x * 2.0) / 3.0,
+        ui.available_height(),
+    );
+    ui.horizontal(|ui| {
+        for (title, stat) in stats {
+            let mut points: Vec<StatPoint> = rows
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| x.map == map)
+                .map(|(i, x)| {
+                    let (normal, endless) = stat(x);
+                    StatPoint {
+                        level: x.level,
+                        normal,
+                        endless,
+                        selected: selected == Some(i),
+                        boss: x.boss != 0,
+                    }
+                })
+                .collect();
+            points.sort_by_key(|x| x.level);
+            stat_chart(ui, size, title, &points);
+        }
+    });
+}
+
+fn stat_chart(ui: &mut egui::Ui, size: Vec2, title: &str, points: &[StatPoint]) {
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let rect = response.rect;
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 4.0, visuals.extreme_bg_color);
+    painter.text(
+        rect.left_top() + Vec2::new(6.0, 4.0),
+        Align2::LEFT_TOP,
+        title,
+        FontId::proportional(12.0),
+        visuals.text_color(),
+    );
+    if points.is_empty() {
+        return;
+    }
+
+    let plot = Rect::from_min_max(
+        rect.left_top() + Vec2::new(36.0, 22.0),
+        rect.right_bottom() - Vec2::new(10.0, 18.0),
+    );
+    let min_level = points.first().unwrap().level;
+    let max_level = points.last().unwrap().level.max(min_level + 1);
+    let max_value = points
+        .iter()
+        .map(|x| x.normal.max(x.endless))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let to_screen = |level: i32, value: i32| {
+        Pos2::new(
+            egui::remap(
+                level as f32,
+                min_level as f32..=max_level as f32,
+                plot.x_range(),
+            ),
+            egui::remap(
+                value as f32,
+                0.0..=max_value as f32,
+                plot.bottom()..=plot.top(),
+            ),
+        )
+    };
+
+    let axis = Stroke::new(1.0, visuals.weak_text_color());
+    painter.line_segment([plot.left_bottom(), plot.right_bottom()], axis);
+    painter.line_segment([plot.left_bottom(), plot.left_top()], axis);
+    painter.text(
+        plot.left_top(),
+        Align2::RIGHT_CENTER,
+        max_value.to_string(),
+        FontId::proportional(10.0),
+        visuals.weak_text_color(),
+    );
+    painter.text(
+        plot.left_bottom() + Vec2::new(0.0, 2.0),
+        Align2::CENTER_TOP,
+        min_level.to_string(),
+        FontId::proportional(10.0),
+        visuals.weak_text_color(),
+    );
+    painter.text(
+        plot.right_bottom() + Vec2::new(0.0, 2.0),
+        Align2::CENTER_TOP,
+        max_level.to_string(),
+        FontId::proportional(10.0),
+        visuals.weak_text_color(),
+    );
+
+    let mut hovered = None;
+    for (color, value) in [
+        (
+            NORMAL_COLOR,
+            (|x: &StatPoint| x.normal) as fn(&StatPoint) -> i32,
+        ),
+        (ENDLESS_COLOR, |x: &StatPoint| x.endless),
+    ] {
+        let line: Vec<Pos2> = points
+            .iter()
+            .map(|x| to_screen(x.level, value(x)))
+            .collect();
+        painter.add(egui::Shape::line(line.clone(), Stroke::new(1.5, color)));
+        for (pos, point) in line.into_iter().zip(points) {
+            let radius = if point.selected { 5.0 } else { 3.0 };
+            let fill = if point.boss { BOSS_COLOR } else { color };
+            painter.circle_filled(pos, radius, fill);
+            if point.selected {
+                painter.circle_stroke(pos, radius + 2.0, Stroke::new(1.5, visuals.text_color()));
+            }
+            if let Some(hover) = response.hover_pos()
+                && hover.distance(pos) < 6.0
+            {
+                hovered = Some((pos, point.level, value(point)));
+            }
+        }
+    }
+
+    if let Some((pos, level, value)) = hovered {
+        painter.text(
+            pos + Vec2::new(6.0, -6.0),
+            Align2::LEFT_BOTTOM,
+            format!("关卡 {}: {}", level, value),
+            FontId::proportional(11.0),
+            visuals.strong_text_color(),
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use super::libpage::*;
+use crate::bean::Localization;
+use crate::data::TableData;
+use crate::page::Page;
+use crate::{
+    Align, CentralPanel, ComboBox, Layout, MyApp, ProgressBar, ScrollArea, Sense, SidePanel,
+    TextEdit, egui,
+};
+use egui_extras::{Column, TableBuilder};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    Cn,
+    En,
+    Jp,
+    Cnt,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [Language::Cn, Language::En, Language::Jp, Language::Cnt];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Cn => "简体中文",
+            Language::En => "English",
+            Language::Jp => "日本語",
+            Language::Cnt => "繁體中文",
+        }
+    }
+
+    pub fn get<'a>(&self, row: &'a Localization) -> &'a String {
+        match self {
+            Language::Cn => &row.cn,
+            Language::En => &row.en,
+            Language::Jp => &row.jp,
+            Language::Cnt => &row.cnt,
+        }
+    }
+
+    pub fn get_mut<'a>(&self, row: &'a mut Localization) -> &'a mut String {
+        match self {
+            Language::Cn => &mut row.cn,
+            Language::En => &mut row.en,
+            Language::Jp => &mut row.jp,
+            Language::Cnt => &mut row.cnt,
+        }
+    }
+
+    pub fn is_missing(&self, row: &Localization) -> bool {
+        self.get(row).trim().is_empty()
+    }
+}
+
+/// 已翻译的比例
+pub fn coverage(rows: &[Localization], language: Language) -> f32 {
+    if rows.is_empty() {
+        return 1.0;
+    }
+    let translated = rows.iter().filter(|x| !language.is_missing(x)).count();
+    translated as f32 / rows.len() as f32
+}
+
+/// 其他表中对多语言id的一处引用
+#[derive(Debug, Clone, PartialEq)]
+pub struct L10nRef {
+    pub l10n_id: i32,
+    pub page: Page,
+    pub index: usize,
+    pub row_id: i32,
+    pub field: &'static str,
+}
+
+/// 遍历所有表中引用多语言id的字段
+pub fn l10n_references(table_data: &TableData) -> Vec<L10nRef> {
+    let mut refs = Vec::new();
+    let mut push = |page: &Page, index, row_id, field, l10n_id| {
+        refs.push(L10nRef {
+            l10n_id,
+            page: page.clone(),
+            index,
+            row_id,
+            field,
+        })
+    };
+    for (i, x) in table_data.relics.iter().enumerate() {
+        push(&Page::Relic, i, x.id, "NameId", x.name_id);
+        push(&Page::Relic, i, x.id, "DescId", x.desc_id);
+        push(&Page::Relic, i, x.id, "OtherDescId", x.other_desc_id);
+        push(&Page::Relic, i, x.id, "EventTip", x.event_tip);
+    }
+    for (i, x) in table_data.elements.iter().enumerate() {
+        push(&Page::Element, i, x.id, "NameId", x.name_id);
+        push(&Page::Element, i, x.id, "DescId", x.desc_id);
+        push(&Page::Element, i, x.id, "EventTip", x.event_tip);
+    }
+    for (i, x) in table_data.enemies.iter().enumerate() {
+        push(&Page::Enemy, i, x.id, "NameId", x.name_id);
+        push(&Page::Enemy, i, x.id, "DescId", x.desc_id);
+    }
+    for (i, x) in table_data.race_attributes.iter().enumerate() {
+        push(&Page::RaceAttribute, i, x.id, "NameID", x.name_id);
+        push(&Page::RaceAttribute, i, x.id, "DescID", x.desc_id);
+    }
+    // 0 表示未填写，不算引用
+    refs.retain(|x| x.l10n_id != 0);
+    refs
+}
+
+#[derive(Default)]
+pub struct L10nPageState {
+    selected: Option<usize>,
+    missing: Option<Language>,
+    search: String,
+}
+
+pub fn render(ui: &mut egui::Ui, app: &mut MyApp) {
+    let state = &mut app.app_state.l10n_page;
+
+    let mut scroll_to = app.app_state.scroll_to_row.take();
+    if scroll_to.is_some() {
+        state.selected = scroll_to;
+        state.missing = None;
+        state.search.clear();
+    }
+    state.selected = state
+        .selected
+        .filter(|&i| i < app.app_state.table_data.l10n.len());
+
+    let refs = l10n_references(&app.app_state.table_data);
+    let mut ref_counts: HashMap<i32, usize> = HashMap::new();
+    for r in &refs {
+        *ref_counts.entry(r.l10n_id).or_default() += 1;
+    }
+
+    let rows = &mut app.app_state.table_data.l10n;
+    ui.horizontal(|ui| {
+        if let Some(action) = row_toolbar(ui, state.selected.is_some()) {
+            state.selected = apply_row_action(
+                rows,
+                state.selected,
+                action,
+                |id| Localization {
+                    id,
+                    ..Default::default()
+                },
+                |row, id| row.id = id,
+            );
+            scroll_to = state.selected;
+        }
+        ui.separator();
+        ui.label("筛选:");
+        ComboBox::from_id_salt("l10n_missing")
+            .selected_text(match state.missing {
+                Some(language) => format!("缺少 {}", language.name()),
+                None => "全部".to_string(),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut state.missing, None, "全部");
+                for language in Language::ALL {
+                    ui.selectable_value(
+                        &mut state.missing,
+                        Some(language),
+                        format!("缺少 {}", language.name()),
+                    );
+                }
+            });
+        ui.add(
+            TextEdit::singleline(&mut state.search)
+                .hint_text("搜索id或文本")
+                .desired_width(160.0),
+        );
+    });
+    ui.horizontal(|ui| {
+        for language in Language::ALL {
+            let value = coverage(rows, language);
+            ui.label(language.name());
+            ui.add(
+                ProgressBar::new(value)
+                    .desired_width(100.0)
+                    .text(format!("{:.1}%", value * 100.0)),
+            );
+        }
+    });
+    ui.separator();
+
+    let mut jump = None;
+    SidePanel::right("l10n_references")
+        .resizable(true)
+        .default_width(260.0)
+        .show_inside(ui, |ui| {
+            ui.strong("引用");
+            ui.separator();
+            let Some(id) = state.selected.map(|i| rows[i].id) else {
+                ui.label("请选择一条文本");
+                return;
+            };
+            ScrollArea::vertical().show(ui, |ui| {
+                let mut any = false;
+                for r in refs.iter().filter(|r| r.l10n_id == id) {
+                    any = true;
+                    let text = format!("{} #{} · {}", r.page.get_info().title, r.row_id, r.field);
+                    if ui.link(text).clicked() {
+                        jump = Some((r.page.clone(), r.index));
+                    }
+                }
+                if !any {
+                    ui.label("没有表引用这条文本");
+                }
+            });
+        });
+
+    CentralPanel::default().show_inside(ui, |ui| {
+        let search = state.search.trim();
+        let order: Vec<usize> = rows
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| state.missing.is_none_or(|language| language.is_missing(x)))
+            .filter(|(_, x)| {
+                search.is_empty()
+                    || x.id.to_string().contains(search)
+                    || Language::ALL.iter().any(|l| l.get(x).contains(search))
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .sense(Sense::click())
+            .cell_layout(Layout::left_to_right(Align::Center))
+            .column(Column::auto().at_least(60.0))
+            .columns(Column::initial(180.0).clip(true), Language::ALL.len())
+            .column(Column::remainder().at_least(40.0));
+        if let Some(target) = scroll_to.and_then(|i| order.iter().position(|&x| x == i)) {
+            table = table.scroll_to_row(target, Some(Align::Center));
+        }
+        table
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Id");
+                });
+                for language in Language::ALL {
+                    header.col(|ui| {
+                        ui.strong(language.name());
+                    });
+                }
+                header.col(|ui| {
+                    ui.strong("引用");
+                });
+            })
+            .body(|body| {
+                body.rows(22.0, order.len(), |mut row| {
+                    let index = order[row.index()];
+                    row.set_selected(state.selected == Some(index));
+                    let text = &mut rows[index];
+                    row.col(|ui| {
+                        ui.add(egui::DragValue::new(&mut text.id));
+                    });
+                    for language in Language::ALL {
+                        row.col(|ui| {
+                            ui.add(
+                                TextEdit::singleline(language.get_mut(text))
+                                    .desired_width(f32::INFINITY),
+                            );
+                        });
+                    }
+                    row.col(|ui| {
+                        ui.label(ref_counts.get(&text.id).copied().unwrap_or(0).to_string());
+                    });
+                    if row.response().clicked() {
+                        state.selected = Some(index);
+                    }
+                });
+            });
+    });
+
+    if let Some((page, index)) = jump {
+        app.app_data.selected_page = Some(page);
+        app.app_state.scroll_to_row = Some(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bean::{Enemy, Relics};
+
+    #[test]
+    fn coverage_counts_blank_as_missing() {
+        let rows = vec![
+            Localization {
+                id: 1,
+                cn: "猫".to_string(),
+                en: "Cat".to_string(),
+                ..Default::default()
+            },
+            Localization {
+                id: 2,
+                cn: "狗".to_string(),
+                en: "  ".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(coverage(&rows, Language::Cn), 1.0);
+        assert_eq!(coverage(&rows, Language::En), 0.5);
+        assert_eq!(coverage(&rows, Language::Jp), 0.0);
+        assert_eq!(coverage(&[], Language::Jp), 1.0);
+    }
+
+    #[test]
+    fn references_skip_unset_ids() {
+        let table_data = TableData {
+            relics: vec![Relics {
+                id: 5,
+                name_id: 100,
+                event_tip: 101,
+                ..Default::default()
+            }],
+            enemies: vec![Enemy {
+                id: 9,
+                desc_id: 100,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let refs: Vec<_> = l10n_references(&table_data)
+            .into_iter()
+            .map(|r| (r.l10n_id, r.page, r.row_id, r.field))
+            .collect();
+        assert_eq!(
+            refs,
+            vec![
+                (100, Page::Relic, 5, "NameId"),
+                (101, Page::Relic, 5, "EventTip"),
+                (100, Page::Enemy, 9, "DescId"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,405 @@
+use crate::assets::{ImageIndex, THUMBNAIL_SIZE, thumbnail_button};
+use crate::bean::{ERaceType, ERare, Etip, Localization, TableRow};
+use crate::sound::SoundRegistry;
+use crate::{Color32, ComboBox, DragValue, Id, Response, RichText, ScrollArea, Sense, egui};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+/// 表格排序状态
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SortState {
+    pub column: usize,
+    pub descending: bool,
+}
+
+impl SortState {
+    /// 按当前排序列返回行的显示顺序
+    pub fn sorted_indices<T>(
+        &self,
+        rows: &[T],
+        compare: fn(&T, &T, usize) -> Ordering,
+    ) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..rows.len()).collect();
+        indices.sort_by(|&a, &b| {
+            let ordering = compare(&rows[a], &rows[b], self.column);
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        indices
+    }
+}
+
+/// 可点击排序的表头
+pub fn sort_header(ui: &mut egui::Ui, sort: &mut SortState, column: usize, title: &str) {
+    let arrow = if sort.column != column {
+        ""
+    } else if sort.descending {
+        " ⏷"
+    } else {
+        " ⏶"
+    };
+    let response = ui.add(
+        egui::Label::new(RichText::new(format!("{}{}", title, arrow)).strong())
+            .sense(Sense::click()),
+    );
+    if response.clicked() {
+        if sort.column == column {
+            sort.descending = !sort.descending;
+        } else {
+            sort.column = column;
+            sort.descending = false;
+        }
+    }
+}
+
+/// 表格上方的行操作
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowAction {
+    Add,
+    Duplicate,
+    Delete,
+}
+
+pub fn row_toolbar(ui: &mut egui::Ui, has_selection: bool) -> Option<RowAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if ui.button("➕ 新增").clicked() {
+            action = Some(RowAction::Add);
+        }
+        ui.add_enabled_ui(has_selection, |ui| {
+            if ui.button("📋 复制").clicked() {
+                action = Some(RowAction::Duplicate);
+            }
+            if ui.button("🗑 删除").clicked() {
+                action = Some(RowAction::Delete);
+            }
+        });
+    });
+    action
+}
+
+/// 新行使用的id，最大id已经是 i32::MAX 时向下查找没有使用的id
+pub fn next_id<T: TableRow>(rows: &[T]) -> i32 {
+    let Some(max) = rows.iter().map(|x| x.id()).max() else {
+        return 1;
+    };
+    max.checked_add(1).unwrap_or_else(|| {
+        let used: HashSet<i32> = rows.iter().map(|x| x.id()).collect();
+        // 表格的行数远小于id的范围，总能找到
+        (i32::MIN..i32::MAX)
+            .rev()
+            .find(|x| !used.contains(x))
+            .expect("没有可用的id")
+    })
+}
+
+/// 执行行操作，返回操作后应选中的行
+pub fn apply_row_action<T: TableRow + Clone>(
+    rows: &mut Vec<T>,
+    selected: Option<usize>,
+    action: RowAction,
+    new_row: impl FnOnce(i32) -> T,
+    set_id: fn(&mut T, i32),
+) -> Option<usize> {
+    match action {
+        RowAction::Add => {
+            rows.push(new_row(next_id(rows)));
+            Some(rows.len() - 1)
+        }
+        RowAction::Duplicate => {
+            let index = selected.filter(|&i| i < rows.len())?;
+            let mut row = rows[index].clone();
+            set_id(&mut row, next_id(rows));
+            rows.insert(index + 1, row);
+            Some(index + 1)
+        }
+        RowAction::Delete => {
+            let index = selected.filter(|&i| i < rows.len())?;
+            rows.remove(index);
+            if rows.is_empty() {
+                None
+            } else {
+                Some(index.min(rows.len() - 1))
+            }
+        }
+    }
+}
+
+/// 名称列显示的文本
+pub fn l10n_name(l10n: &[Localization], name_id: i32) -> String {
+    match l10n.iter().find(|x| x.id == name_id) {
+        Some(text) => format!("{} {}", name_id, text.cn),
+        None => name_id.to_string(),
+    }
+}
+
+/// 表单中的字段名
+pub fn field_label(ui: &mut egui::Ui, name: &str, desc: &str) -> Response {
+    ui.label(name).on_hover_text(desc)
+}
+
+pub fn int_edit(ui: &mut egui::Ui, value: &mut i32) -> bool {
+    ui.add(DragValue::new(value)).changed()
+}
+
+/// 0/1 标记字段
+pub fn flag_edit(ui: &mut egui::Ui, value: &mut i32) -> bool {
+    let mut checked = *value != 0;
+    if ui.checkbox(&mut checked, "").changed() {
+        *value = checked as i32;
+        return true;
+    }
+    false
+}
+
+/// 图标缩略图，Image_Mod 中没有文件时显示警告
+pub fn icon_preview(ui: &mut egui::Ui, icon: &str, images: &ImageIndex) {
+    if let Some(image) = images.find(icon) {
+        ui.add(image.thumbnail(20.0)).on_hover_ui(|ui| {
+            ui.add(image.thumbnail(THUMBNAIL_SIZE * 2.0));
+        });
+    } else if images.is_missing(icon) {
+        ui.colored_label(Color32::RED, "⚠")
+            .on_hover_text("Image_Mod 中没有这张图片");
+    }
+}
+
+/// 表格中的图标列
+pub fn icon_label(ui: &mut egui::Ui, icon: &str, images: &ImageIndex) {
+    icon_preview(ui, icon, images);
+    ui.label(icon);
+}
+
+/// 图标编辑器，可以从 Image_Mod 的缩略图中选择
+pub fn icon_edit(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    value: &mut String,
+    images: &ImageIndex,
+) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        icon_preview(ui, value, images);
+        changed |= ui
+            .add(egui::TextEdit::singleline(value).desired_width(140.0))
+            .changed();
+        ui.menu_button("选择", |ui| {
+            if images.images.is_empty() {
+                ui.label("Image_Mod 中没有图片");
+                return;
+            }
+            ui.set_width(6.0 * (THUMBNAIL_SIZE + 16.0));
+            ScrollArea::vertical()
+                .id_salt(id_salt)
+                .max_height(320.0)
+                .show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for image in &images.images {
+                            if thumbnail_button(ui, image, image.name == *value).clicked() {
+                                *value = image.name.clone();
+                                changed = true;
+                                ui.close();
+                            }
+                        }
+                    });
+                });
+        });
+    });
+    changed
+}
+
+/// 音效id编辑器，可以从 Sound_Mod 注册表中选择，0 表示没有音效
+pub fn sound_edit(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    value: &mut i32,
+    sounds: &SoundRegistry,
+) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= ui.add(DragValue::new(value)).changed();
+        let selected = match sounds.find(*value) {
+            Some(entry) => entry.file.clone(),
+            None if *value == 0 => "无".to_string(),
+            None => "未注册".to_string(),
+        };
+        ComboBox::from_id_salt(id_salt)
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                changed |= ui.selectable_value(value, 0, "无").changed();
+                for entry in &sounds.entries {
+                    let text = match sounds.info(&entry.file) {
+                        Some(Ok(info)) => {
+                            format!("{} {} ({})", entry.id, entry.file, info.summary())
+                        }
+                        _ => format!("{} {}", entry.id, entry.file),
+                    };
+                    changed |= ui.selectable_value(value, entry.id, text).changed();
+                }
+            });
+        if *value != 0 && sounds.dir().is_some() && sounds.find(*value).is_none() {
+            ui.colored_label(Color32::RED, "⚠")
+                .on_hover_text("Sound_Mod 注册表中没有这个音效");
+        }
+    });
+    changed
+}
+
+/// 整数列表编辑器
+pub fn int_list_edit(ui: &mut egui::Ui, values: &mut Vec<i32>) -> bool {
+    let mut changed = false;
+    let mut remove = None;
+    ui.horizontal_wrapped(|ui| {
+        for (i, value) in values.iter_mut().enumerate() {
+            changed |= ui.add(DragValue::new(value)).changed();
+            if ui.small_button("×").clicked() {
+                remove = Some(i);
+            }
+        }
+        if ui.small_button("+").clicked() {
+            values.push(0);
+            changed = true;
+        }
+    });
+    if let Some(i) = remove {
+        values.remove(i);
+        changed = true;
+    }
+    changed
+}
+
+pub fn etip_edit(ui: &mut egui::Ui, id_salt: impl std::hash::Hash, value: &mut Etip) -> bool {
+    let mut changed = false;
+    ComboBox::from_id_salt(id_salt)
+        .selected_text(format!("{:?}", value))
+        .show_ui(ui, |ui| {
+            for tip in Etip::ALL {
+                changed |= ui
+                    .selectable_value(value, tip, format!("{:?}", tip))
+                    .changed();
+            }
+        });
+    changed
+}
+
+/// 特殊描述类型列表编辑器
+pub fn etip_list_edit(ui: &mut egui::Ui, id_salt: &str, values: &mut Vec<Etip>) -> bool {
+    let mut changed = false;
+    let mut remove = None;
+    ui.horizontal_wrapped(|ui| {
+        for (i, value) in values.iter_mut().enumerate() {
+            changed |= etip_edit(ui, Id::new(id_salt).with(i), value);
+            if ui.small_button("×").clicked() {
+                remove = Some(i);
+            }
+        }
+        if ui.small_button("+").clicked() {
+            values.push(Etip::None);
+            changed = true;
+        }
+    });
+    if let Some(i) = remove {
+        values.remove(i);
+        changed = true;
+    }
+    changed
+}
+
+/// 种族下拉框
+pub fn race_edit(ui: &mut egui::Ui, id_salt: &str, value: &mut ERaceType) -> bool {
+    let mut changed = false;
+    ComboBox::from_id_salt(id_salt)
+        .selected_text(format!("{:?}", value))
+        .show_ui(ui, |ui| {
+            for race in ERaceType::ALL {
+                changed |= ui
+                    .selectable_value(value, race, format!("{:?}", race))
+                    .changed();
+            }
+        });
+    changed
+}
+
+/// 稀有度下拉框
+pub fn rare_edit(ui: &mut egui::Ui, id_salt: &str, value: &mut i32) -> bool {
+    let mut changed = false;
+    ComboBox::from_id_salt(id_salt)
+        .selected_text(rare_name(*value))
+        .show_ui(ui, |ui| {
+            for rare in ERare::ALL {
+                changed |= ui
+                    .selectable_value(value, rare as i32, rare.name())
+                    .changed();
+            }
+        });
+    changed
+}
+
+pub fn rare_name(value: i32) -> String {
+    ERare::from_i32(value).map_or_else(|| value.to_string(), |x| x.name().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bean::Relics;
+
+    fn relic(id: i32) -> Relics {
+        Relics {
+            id,
+            ..Default::default()
+        }
+    }
+
+    fn ids(rows: &[Relics]) -> Vec<i32> {
+        rows.iter().map(|x| x.id).collect()
+    }
+
+    #[test]
+    fn row_actions_keep_selection_valid() {
+        let mut rows = vec![relic(3), relic(7)];
+        let new_row = |id| relic(id);
+        let set_id = |row: &mut Relics, id| row.id = id;
+
+        let selected = apply_row_action(&mut rows, None, RowAction::Add, new_row, set_id);
+        assert_eq!((ids(&rows), selected), (vec![3, 7, 8], Some(2)));
+
+        let selected = apply_row_action(&mut rows, Some(0), RowAction::Duplicate, new_row, set_id);
+        assert_eq!((ids(&rows), selected), (vec![3, 9, 7, 8], Some(1)));
+
+        let selected = apply_row_action(&mut rows, Some(3), RowAction::Delete, new_row, set_id);
+        assert_eq!((ids(&rows), selected), (vec![3, 9, 7], Some(2)));
+
+        let selected = apply_row_action(&mut rows, None, RowAction::Delete, new_row, set_id);
+        assert_eq!((ids(&rows), selected), (vec![3, 9, 7], None));
+    }
+
+    #[test]
+    fn next_id_does_not_overflow() {
+        assert_eq!(next_id::<Relics>(&[]), 1);
+        let mut rows = vec![relic(i32::MAX), relic(i32::MAX - 1)];
+        assert_eq!(next_id(&rows), i32::MAX - 2);
+
+        let new_row = |id| relic(id);
+        let set_id = |row: &mut Relics, id| row.id = id;
+        apply_row_action(&mut rows, Some(0), RowAction::Duplicate, new_row, set_id);
+        apply_row_action(&mut rows, None, RowAction::Add, new_row, set_id);
+        assert_eq!(
+            ids(&rows),
+            vec![i32::MAX, i32::MAX - 2, i32::MAX - 1, i32::MAX - 3]
+        );
+    }
+
+    #[test]
+    fn sorted_indices_respects_direction() {
+        let rows = vec![relic(2), relic(1), relic(3)];
+        let compare = |a: &Relics, b: &Relics, _| a.id.cmp(&b.id);
+        let mut sort = SortState::default();
+        assert_eq!(sort.sorted_indices(&rows, compare), vec![1, 0, 2]);
+        sort.descending = true;
+        assert_eq!(sort.sorted_indices(&rows, compare), vec![2, 0, 1]);
+    }
+}
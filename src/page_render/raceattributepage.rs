@@ -0,0 +1,303 @@
+use super::libpage::*;
+use crate::assets::ImageIndex;
+use crate::bean::{ERaceType, Localization, RaceAttribute};
+use crate::data::TableData;
+use crate::{CentralPanel, Color32, MyApp, RichText, ScrollArea, SidePanel, TextEdit, egui};
+
+#[derive(Default)]
+pub struct RaceAttributePageState {
+    selected: Option<usize>,
+    new_name: String,
+    new_desc: String,
+}
+
+/// 种族在当前数据中的使用情况
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RaceSlot {
+    pub race: ERaceType,
+    /// 对应的种族属性行
+    pub attribute: Option<usize>,
+    /// 使用该种族的单位数量
+    pub elements: usize,
+}
+
+impl RaceSlot {
+    pub fn is_free(&self) -> bool {
+        self.attribute.is_none() && self.elements == 0
+    }
+}
+
+/// 列出所有种族及其使用情况
+pub fn race_slots(table_data: &TableData) -> Vec<RaceSlot> {
+    ERaceType::ALL
+        .into_iter()
+        .map(|race| RaceSlot {
+            race,
+            attribute: table_data
+                .race_attributes
+                .iter()
+                .position(|x| x.id == race as i32),
+            elements: table_data
+                .elements
+                .iter()
+                .filter(|x| x.race_type == race || x.other_race == race)
+                .count(),
+        })
+        .collect()
+}
+
+/// 在空闲的模组种族位上创建种族，同时创建名称和描述的多语言文本，返回新行的下标
+pub fn assign_race(table_data: &mut TableData, race: ERaceType, name: &str, desc: &str) -> usize {
+    // 每次添加后再取下一个id，最大id接近 i32::MAX 时也不会重复
+    let mut add_text = |text: &str| {
+        let id = next_id(&table_data.l10n);
+        table_data.l10n.push(Localization {
+            id,
+            cn: text.to_string(),
+            ..Default::default()
+        });
+        id
+    };
+    let name_id = add_text(name);
+    let desc_id = add_text(desc);
+    table_data.race_attributes.push(RaceAttribute {
+        id: race as i32,
+        icon: String::new(),
+        name_id,
+        desc_id,
+    });
+    table_data.race_attributes.len() - 1
+}
+
+pub fn render(ui: &mut egui::Ui, app: &mut MyApp) {
+    let state = &mut app.app_state.race_attribute_page;
+    let table_data = &mut app.app_state.table_data;
+
+    let scroll_to = app.app_state.scroll_to_row.take();
+    if scroll_to.is_some() {
+        state.selected = scroll_to;
+    }
+    state.selected = state
+        .selected
+        .filter(|&i| i < table_data.race_attributes.len());
+
+    ui.horizontal(|ui| {
+        ui.label("新种族名称:");
+        ui.add(TextEdit::singleline(&mut state.new_name).desired_width(120.0));
+        ui.label("描述:");
+        ui.add(TextEdit::singleline(&mut state.new_desc).desired_width(240.0));
+        ui.label(RichText::new("填写后点击空闲模组位的“分配”").weak());
+    });
+    ui.separator();
+
+    SidePanel::right("race_detail")
+        .resizable(true)
+        .default_width(360.0)
+        .show_inside(ui, |ui| {
+            ScrollArea::vertical().show(ui, |ui| match state.selected {
+                Some(index) => {
+                    race_form(ui, table_data, index, &app.app_state.images);
+                    ui.add_space(10.0);
+                    if ui.button("🗑 删除").clicked() {
+                        table_data.race_attributes.remove(index);
+                        state.selected = None;
+                    }
+                }
+                None => {
+                    ui.label("请在左侧选择一个种族");
+                }
+            });
+        });
+
+    CentralPanel::default().show_inside(ui, |ui| {
+        let slots = race_slots(table_data);
+        let free_mod_slots = slots
+            .iter()
+            .filter(|x| x.race.is_mod_slot() && x.is_free())
+            .count();
+        ui.label(format!("空闲模组种族位: {} / 8", free_mod_slots));
+
+        ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("race_slots")
+                .num_columns(5)
+                .striped(true)
+                .show(ui, |ui| {
+                    for title in ["种族", "值", "名称", "单位", "状态"] {
+                        ui.strong(title);
+                    }
+                    ui.end_row();
+
+                    for slot in &slots {
+                        let selected = slot.attribute.is_some() && slot.attribute == state.selected;
+                        if ui
+                            .selectable_label(selected, format!("{:?}", slot.race))
+                            .clicked()
+                            && slot.attribute.is_some()
+                        {
+                            state.selected = slot.attribute;
+                        }
+                        ui.label((slot.race as i32).to_string());
+                        ui.label(slot.attribute.map_or(String::new(), |i| {
+                            l10n_name(&table_data.l10n, table_data.race_attributes[i].name_id)
+                        }));
+                        ui.label(slot.elements.to_string());
+                        if !slot.race.is_mod_slot() {
+                            ui.label(RichText::new("内置").weak());
+                        } else if !slot.is_free() {
+                            ui.colored_label(Color32::from_rgb(230, 160, 60), "已占用");
+                        } else {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(Color32::from_rgb(90, 180, 90), "空闲");
+                                let ready = !state.new_name.trim().is_empty();
+                                if ui
+                                    .add_enabled(ready, egui::Button::new("分配"))
+                                    .on_disabled_hover_text("请先填写新种族名称")
+                                    .clicked()
+                                {
+                                    state.selected = Some(assign_race(
+                                        table_data,
+                                        slot.race,
+                                        state.new_name.trim(),
+                                        state.new_desc.trim(),
+                                    ));
+                                    state.new_name.clear();
+                                    state.new_desc.clear();
+                                }
+                            });
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            // id不属于任何种族的行
+            let orphans: Vec<usize> = table_data
+                .race_attributes
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| !ERaceType::ALL.iter().any(|r| *r as i32 == x.id))
+                .map(|(i, _)| i)
+                .collect();
+            if !orphans.is_empty() {
+                ui.separator();
+                ui.colored_label(Color32::RED, "以下种族属性的id没有对应的种族:");
+                for i in orphans {
+                    let row = &table_data.race_attributes[i];
+                    if ui
+                        .selectable_label(
+                            state.selected == Some(i),
+                            format!("#{} {}", row.id, l10n_name(&table_data.l10n, row.name_id)),
+                        )
+                        .clicked()
+                    {
+                        state.selected = Some(i);
+                    }
+                }
+            }
+        });
+    });
+}
+
+fn race_form(
+    ui: &mut egui::Ui,
+    table_data: &mut TableData,
+    index: usize,
+    images: &ImageIndex,
+) -> bool {
+    let mut changed = false;
+    let row = &mut table_data.race_attributes[index];
+    egui::Grid::new("race_form")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            field_label(ui, "Id", "这是id");
+            changed |= int_edit(ui, &mut row.id);
+            ui.end_row();
+
+            field_label(ui, "Icon", "图标");
+            changed |= icon_edit(ui, "race_icon", &mut row.icon, images);
+            ui.end_row();
+
+            field_label(ui, "NameID", "多语言ID");
+            changed |= int_edit(ui, &mut row.name_id);
+            ui.end_row();
+
+            field_label(ui, "DescID", "描述");
+            changed |= int_edit(ui, &mut row.desc_id);
+            ui.end_row();
+        });
+
+    // 直接编辑引用的中文文本
+    let (name_id, desc_id) = (row.name_id, row.desc_id);
+    ui.separator();
+    for (title, id) in [("名称", name_id), ("描述", desc_id)] {
+        match table_data.l10n.iter_mut().find(|x| x.id == id) {
+            Some(text) => {
+                ui.label(format!("{} ({})", title, id));
+                changed |= ui.text_edit_multiline(&mut text.cn).changed();
+            }
+            None => {
+                ui.colored_label(Color32::RED, format!("{} 多语言id {} 不存在", title, id));
+            }
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bean::Element;
+
+    #[test]
+    fn slots_track_attributes_and_elements() {
+        let mut table_data = TableData {
+            elements: vec![Element {
+                id: 1,
+                other_race: ERaceType::Mod2,
+                ..Default::default()
+            }],
+            l10n: vec![Localization {
+                id: 10,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let index = assign_race(&mut table_data, ERaceType::Mod1, "史莱姆", "黏糊糊");
+        assert_eq!(table_data.race_attributes[index].id, 14);
+        assert_eq!(table_data.race_attributes[index].name_id, 11);
+        assert_eq!(table_data.race_attributes[index].desc_id, 12);
+        assert_eq!(table_data.l10n[1].cn, "史莱姆");
+        assert_eq!(table_data.l10n[2].cn, "黏糊糊");
+
+        let free: Vec<ERaceType> = race_slots(&table_data)
+            .into_iter()
+            .filter(|x| x.race.is_mod_slot() && x.is_free())
+            .map(|x| x.race)
+            .collect();
+        assert_eq!(
+            free,
+            vec![
+                ERaceType::Mod3,
+                ERaceType::Mod4,
+                ERaceType::Mod5,
+                ERaceType::Mod6,
+                ERaceType::Mod7,
+                ERaceType::Mod8,
+            ]
+        );
+    }
+
+    #[test]
+    fn new_race_texts_do_not_overflow_ids() {
+        let mut table_data = TableData {
+            l10n: vec![Localization {
+                id: i32::MAX,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let index = assign_race(&mut table_data, ERaceType::Mod1, "史莱姆", "黏糊糊");
+        let race = &table_data.race_attributes[index];
+        assert_eq!((race.name_id, race.desc_id), (i32::MAX - 1, i32::MAX - 2));
+    }
+}
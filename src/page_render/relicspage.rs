@@ -0,0 +1,219 @@
+use super::libpage::*;
+use crate::assets::ImageIndex;
+use crate::bean::Relics;
+use crate::{Align, CentralPanel, Layout, MyApp, ScrollArea, Sense, SidePanel, egui};
+use egui_extras::{Column, TableBuilder};
+use std::cmp::Ordering;
+
+#[derive(Default)]
+pub struct RelicsPageState {
+    selected: Option<usize>,
+    sort: SortState,
+}
+
+const COLUMNS: [&str; 8] = [
+    "Id",
+    "名称",
+    "图标",
+    "稀有度",
+    "启用",
+    "锁定",
+    "专属角色",
+    "触发条件",
+];
+
+fn compare(a: &Relics, b: &Relics, column: usize) -> Ordering {
+    match column {
+        1 => a.name_id.cmp(&b.name_id),
+        2 => a.icon.cmp(&b.icon),
+        3 => a.rare.cmp(&b.rare),
+        4 => a.enable.cmp(&b.enable),
+        5 => a.lock.cmp(&b.lock),
+        6 => a.role.cmp(&b.role),
+        7 => a.trigger_type.cmp(&b.trigger_type),
+        _ => Ordering::Equal,
+    }
+    .then(a.id.cmp(&b.id))
+}
+
+pub fn render(ui: &mut egui::Ui, app: &mut MyApp) {
+    let state = &mut app.app_state.relics_page;
+    let table_data = &mut app.app_state.table_data;
+    let rows = &mut table_data.relics;
+
+    let mut scroll_to = app.app_state.scroll_to_row.take();
+    if scroll_to.is_some() {
+        state.selected = scroll_to;
+    }
+    state.selected = state.selected.filter(|&i| i < rows.len());
+
+    if let Some(action) = row_toolbar(ui, state.selected.is_some()) {
+        state.selected = apply_row_action(
+            rows,
+            state.selected,
+            action,
+            |id| Relics {
+                id,
+                ..Default::default()
+            },
+            |row, id| row.id = id,
+        );
+        scroll_to = state.selected;
+    }
+    ui.separator();
+
+    SidePanel::right("relic_detail")
+        .resizable(true)
+        .default_width(380.0)
+        .show_inside(ui, |ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                match state.selected.and_then(|i| rows.get_mut(i)) {
+                    Some(row) => {
+                        relic_form(ui, row, &app.app_state.images);
+                    }
+                    None => {
+                        ui.label("请在左侧选择一个遗物");
+                    }
+                }
+            });
+        });
+
+    CentralPanel::default().show_inside(ui, |ui| {
+        let order = state.sort.sorted_indices(rows, compare);
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .sense(Sense::click())
+            .cell_layout(Layout::left_to_right(Align::Center))
+            .columns(Column::auto().at_least(50.0), COLUMNS.len() - 1)
+            .column(Column::remainder());
+        if let Some(target) = scroll_to.and_then(|i| order.iter().position(|&x| x == i)) {
+            table = table.scroll_to_row(target, Some(Align::Center));
+        }
+        table
+            .header(20.0, |mut header| {
+                for (i, title) in COLUMNS.iter().enumerate() {
+                    header.col(|ui| sort_header(ui, &mut state.sort, i, title));
+                }
+            })
+            .body(|body| {
+                body.rows(20.0, order.len(), |mut row| {
+                    let index = order[row.index()];
+                    let relic = &rows[index];
+                    row.set_selected(state.selected == Some(index));
+                    row.col(|ui| {
+                        ui.label(relic.id.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(l10n_name(&table_data.l10n, relic.name_id));
+                    });
+                    row.col(|ui| {
+                        icon_label(ui, &relic.icon, &app.app_state.images);
+                    });
+                    row.col(|ui| {
+                        ui.label(rare_name(relic.rare));
+                    });
+                    row.col(|ui| {
+                        ui.label(if relic.enable != 0 { "✔" } else { "" });
+                    });
+                    row.col(|ui| {
+                        ui.label(if relic.lock != 0 { "🔒" } else { "" });
+                    });
+                    row.col(|ui| {
+                        ui.label(relic.role.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(relic.trigger_type.to_string());
+                    });
+                    if row.response().clicked() {
+                        state.selected = Some(index);
+                    }
+                });
+            });
+    });
+}
+
+fn relic_form(ui: &mut egui::Ui, row: &mut Relics, images: &ImageIndex) -> bool {
+    let mut changed = false;
+    egui::Grid::new("relic_form")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            field_label(ui, "Id", "这是id");
+            changed |= int_edit(ui, &mut row.id);
+            ui.end_row();
+
+            field_label(ui, "Enable", "是否启用");
+            changed |= flag_edit(ui, &mut row.enable);
+            ui.end_row();
+
+            field_label(ui, "Lock", "是否锁定");
+            changed |= flag_edit(ui, &mut row.lock);
+            ui.end_row();
+
+            field_label(ui, "Role", "专属角色");
+            changed |= int_edit(ui, &mut row.role);
+            ui.end_row();
+
+            field_label(ui, "Tutorial", "教程出现");
+            changed |= flag_edit(ui, &mut row.tutorial);
+            ui.end_row();
+
+            field_label(ui, "Icon", "图标");
+            changed |= icon_edit(ui, "relic_icon", &mut row.icon, images);
+            ui.end_row();
+
+            field_label(ui, "SmallIcon", "小图标");
+            changed |= icon_edit(ui, "relic_small_icon", &mut row.small_icon, images);
+            ui.end_row();
+
+            field_label(ui, "NameId", "名称ID");
+            changed |= int_edit(ui, &mut row.name_id);
+            ui.end_row();
+
+            field_label(ui, "DescId", "遗物描述ID");
+            changed |= int_edit(ui, &mut row.desc_id);
+            ui.end_row();
+
+            field_label(ui, "OtherDescId", "剧情描述ID");
+            changed |= int_edit(ui, &mut row.other_desc_id);
+            ui.end_row();
+
+            field_label(ui, "DescTip", "特殊描述类型");
+            changed |= etip_list_edit(ui, "relic_desc_tip", &mut row.desc_tip);
+            ui.end_row();
+
+            field_label(ui, "Rare", "稀有度");
+            changed |= rare_edit(ui, "relic_rare", &mut row.rare);
+            ui.end_row();
+
+            field_label(ui, "Passive", "被动属性");
+            changed |= int_list_edit(ui, &mut row.passive);
+            ui.end_row();
+
+            field_label(ui, "TriggerType", "触发条件");
+            changed |= int_edit(ui, &mut row.trigger_type);
+            ui.end_row();
+
+            field_label(ui, "TriggerParam", "触发条件参数");
+            changed |= int_list_edit(ui, &mut row.trigger_param);
+            ui.end_row();
+
+            field_label(ui, "EventTip", "事件响应语言ID");
+            changed |= int_edit(ui, &mut row.event_tip);
+            ui.end_row();
+
+            field_label(ui, "TriggerAction", "触发行为");
+            changed |= int_edit(ui, &mut row.trigger_action);
+            ui.end_row();
+
+            field_label(ui, "TriggerValue", "加成数值");
+            changed |= int_list_edit(ui, &mut row.trigger_value);
+            ui.end_row();
+
+            field_label(ui, "OtherValue", "其他参数");
+            changed |= int_list_edit(ui, &mut row.other_value);
+            ui.end_row();
+        });
+    changed
+}
@@ -0,0 +1,414 @@
+use crate::SIDEBAR_PAGES;
+use crate::assets::{ImageIndex, icon_references};
+use crate::bean::ModData;
+use crate::data::{TableData, build_tables, json_file_name, read_json_tables, table_data_from};
+use crate::luban::load_tables;
+use crate::sound::SoundRegistry;
+use crate::validation::{Severity, validate, validate_sounds};
+use crate::{RichText, egui};
+use cfg::prelude::{LubanError, Tables};
+use luban_lib::ByteBuf;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+const CONFIG_DIR: &str = "Config_Mod";
+const IMAGE_DIR: &str = "Image_Mod";
+const MOD_DATA_FILE: &str = "moddata.json";
+
+/// 检查结果的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    ModData,
+    Config,
+    Data,
+    Images,
+    Sounds,
+}
+
+impl Category {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::ModData => "moddata.json",
+            Category::Config => "配置表",
+            Category::Data => "数据",
+            Category::Images => "图片",
+            Category::Sounds => "音效",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub category: Category,
+    pub message: String,
+}
+
+/// 上传前对模组文件夹的检查结果，有错误时不能上传
+#[derive(Debug, Clone, Default)]
+pub struct PreflightReport {
+    pub findings: Vec<Finding>,
+}
+
+impl PreflightReport {
+    fn push(&mut self, severity: Severity, category: Category, message: impl Into<String>) {
+        self.findings.push(Finding {
+            severity,
+            category,
+            message: message.into(),
+        });
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|x| x.severity == severity)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "错误 {}，警告 {}",
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        )
+    }
+}
+
+/// 检查模组文件夹：配置表能否解码、图标是否存在、moddata.json 是否有效
+pub fn check_mod_folder(folder: &Path) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    check_mod_data(folder, &mut report);
+    let data = check_config(&folder.join(CONFIG_DIR), &mut report);
+    if let Some(data) = data {
+        for issue in validate(&data) {
+            let message = format!(
+                "{} #{}: {}",
+                issue.page.get_info().title,
+                issue.row_id,
+                issue.message
+            );
+            report.push(issue.severity, Category::Data, message);
+        }
+        check_images(folder, &data, &mut report);
+        check_sounds(folder, &data, &mut report);
+    }
+    report.findings.sort_by_key(|x| (x.category, x.severity));
+    report
+}
+
+fn check_mod_data(folder: &Path, report: &mut PreflightReport) {
+    let path = folder.join(MOD_DATA_FILE);
+    let json = match fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) => {
+            report.push(
+                Severity::Error,
+                Category::ModData,
+                format!("无法读取 {}: {}", path.display(), e),
+            );
+            return;
+        }
+    };
+    let mod_data = match serde_json::from_str::<ModData>(&json) {
+        Ok(mod_data) => mod_data,
+        Err(e) => {
+            report.push(
+                Severity::Error,
+                Category::ModData,
+                format!("格式错误: {}", e),
+            );
+            return;
+        }
+    };
+    if mod_data.name.trim().is_empty() {
+        report.push(Severity::Error, Category::ModData, "ModName 不能为空");
+    }
+    if mod_data.desc.trim().is_empty() {
+        report.push(Severity::Warning, Category::ModData, "ModDesc 为空");
+    }
+    if mod_data.version.trim().is_empty() {
+        report.push(Severity::Warning, Category::ModData, "Version 为空");
+    }
+}
+
+/// 解码 Config_Mod 中的.bytes和json，返回合并后的数据
+fn check_config(dir: &Path, report: &mut PreflightReport) -> Option<TableData> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report.push(
+                Severity::Error,
+                Category::Config,
+                format!("无法读取 {}: {}", dir.display(), e),
+            );
+            return None;
+        }
+    };
+    let json_files: Vec<&str> = SIDEBAR_PAGES.iter().map(json_file_name).collect();
+    let (mut has_bytes, mut has_json) = (false, false);
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.ends_with(".bytes") {
+            has_bytes = true;
+        } else if json_files.contains(&name.as_str()) {
+            has_json = true;
+        } else {
+            report.push(
+                Severity::Warning,
+                Category::Config,
+                format!("{} 不是配置表文件，游戏不会读取", name),
+            );
+        }
+    }
+    if !has_bytes && !has_json {
+        report.push(Severity::Error, Category::Config, "Config_Mod 中没有配置表");
+        return None;
+    }
+
+    let mut data = TableData::default();
+    if has_bytes {
+        match load_tables(&dir.to_string_lossy()) {
+            Ok(tables) => data = table_data_from(&tables),
+            Err(e) => {
+                report.push(
+                    Severity::Error,
+                    Category::Config,
+                    format!("无法解码.bytes: {}", e),
+                );
+                return None;
+            }
+        }
+    }
+    if has_json {
+        let mut messages = VecDeque::new();
+        read_json_tables(dir, &mut data, &mut messages);
+        if !messages.is_empty() {
+            for e in messages {
+                report.push(Severity::Error, Category::Config, e);
+            }
+            return None;
+        }
+        // json中的数据要能按游戏的格式编码再解码
+        check_round_trip(&data, round_trip(&data), report);
+    }
+    Some(data)
+}
+
+/// 解码后的数据要和原来逐字段相同
+fn check_round_trip(
+    data: &TableData,
+    decoded: Result<TableData, LubanError>,
+    report: &mut PreflightReport,
+) {
+    match decoded {
+        Ok(decoded) if decoded.dirty_tables(data).is_empty() => {}
+        Ok(_) => report.push(
+            Severity::Error,
+            Category::Config,
+            "json数据转换为配置表后内容不一致",
+        ),
+        Err(e) => report.push(
+            Severity::Error,
+            Category::Config,
+            format!("json数据无法转换为配置表: {}", e),
+        ),
+    }
+}
+
+/// 在内存中编码再用 cfg::Tables 解码
+fn round_trip(data: &TableData) -> Result<TableData, LubanError> {
+    let mut files = HashMap::new();
+    build_tables(data).save(|name, buf| {
+        files.insert(name.to_string(), buf.copy_data());
+        Ok(())
+    })?;
+    let tables = Tables::new(|name| {
+        files
+            .get(name)
+            .map(|x| ByteBuf::new(x.clone()))
+            .ok_or_else(|| LubanError::Loader(name.to_string()))
+    })?;
+    Ok(table_data_from(&tables))
+}
+
+fn check_images(folder: &Path, data: &TableData, report: &mut PreflightReport) {
+    let refs = icon_references(data);
+    if !folder.join(IMAGE_DIR).is_dir() {
+        if !refs.is_empty() {
+            report.push(
+                Severity::Error,
+                Category::Images,
+                format!("没有 Image_Mod 目录，但有 {} 处引用了图片", refs.len()),
+            );
+        }
+        return;
+    }
+    let mut images = ImageIndex::default();
+    images.sync(folder.to_str());
+    for r in refs {
+        if images.is_missing(&r.icon) {
+            report.push(
+                Severity::Error,
+                Category::Images,
+                format!(
+                    "{} #{} 的 {} 引用的图片 {} 不在 Image_Mod 中",
+                    r.page.get_info().title,
+                    r.row_id,
+                    r.field,
+                    r.icon
+                ),
+            );
+        }
+    }
+}
+
+fn check_sounds(folder: &Path, data: &TableData, report: &mut PreflightReport) {
+    let mut sounds = SoundRegistry::default();
+    if let Some(e) = sounds.sync(folder.to_str()) {
+        report.push(Severity::Error, Category::Sounds, e);
+        return;
+    }
+    if !sounds.dir().is_some_and(Path::exists) {
+        return;
+    }
+    for e in sounds.validate() {
+        report.push(Severity::Error, Category::Sounds, e);
+    }
+    for issue in validate_sounds(data, &sounds) {
+        let message = format!(
+            "{} #{}: {}",
+            issue.page.get_info().title,
+            issue.row_id,
+            issue.message
+        );
+        report.push(issue.severity, Category::Sounds, message);
+    }
+}
+
+/// 按类别显示检查结果
+pub fn render_report(ui: &mut egui::Ui, report: &PreflightReport) {
+    if report.findings.is_empty() {
+        ui.label("没有发现问题");
+        return;
+    }
+    let mut category = None;
+    for finding in &report.findings {
+        if category != Some(finding.category) {
+            category = Some(finding.category);
+            ui.strong(finding.category.name());
+        }
+        ui.horizontal_wrapped(|ui| {
+            ui.label(RichText::new(finding.severity.name()).color(finding.severity.color()));
+            ui.label(&finding.message);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bean::{Element, ElementAttribute, Localization};
+    use crate::data::write_luban_bytes;
+    use crate::testutil::TempDir;
+
+    /// 有效的模组文件夹，元素1引用了图标cat
+    fn mod_folder() -> TempDir {
+        let root = TempDir::new("preflight");
+        let data = TableData {
+            elements: vec![Element {
+                id: 1,
+                icon: "cat".to_string(),
+                ..Default::default()
+            }],
+            l10n: vec![Localization {
+                id: 1,
+                cn: "猫".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        write_luban_bytes(&root.join(CONFIG_DIR), &data).unwrap();
+        fs::write(
+            root.join(MOD_DATA_FILE),
+            r#"{"ModName": "猫猫", "ModDesc": "", "Version": "1.0.0"}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join(IMAGE_DIR)).unwrap();
+        fs::write(root.join(IMAGE_DIR).join("cat.png"), b"png").unwrap();
+        root
+    }
+
+    fn errors(report: &PreflightReport) -> Vec<&Finding> {
+        report
+            .findings
+            .iter()
+            .filter(|x| x.severity == Severity::Error)
+            .collect()
+    }
+
+    #[test]
+    fn empty_folder_reports_mod_data_and_config() {
+        let root = TempDir::new("preflight");
+        let report = check_mod_folder(root.path());
+        assert!(report.has_errors());
+        let categories: Vec<Category> = report.findings.iter().map(|x| x.category).collect();
+        assert_eq!(categories, [Category::ModData, Category::Config]);
+    }
+
+    #[test]
+    fn valid_folder_only_warns_about_empty_description() {
+        let root = mod_folder();
+        let report = check_mod_folder(root.path());
+        assert!(!report.has_errors(), "{:?}", report.findings);
+        assert_eq!(report.count(Severity::Warning), 1);
+    }
+
+    #[test]
+    fn missing_image_dir_is_an_error() {
+        let root = mod_folder();
+        fs::remove_dir_all(root.join(IMAGE_DIR)).unwrap();
+        let report = check_mod_folder(root.path());
+        let errors = errors(&report);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].category, Category::Images);
+        assert!(errors[0].message.contains("没有 Image_Mod 目录"));
+    }
+
+    #[test]
+    fn broken_json_is_a_config_error() {
+        let root = mod_folder();
+        fs::write(root.join(CONFIG_DIR).join("element_mod.json"), "[{").unwrap();
+        let report = check_mod_folder(root.path());
+        let errors = errors(&report);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].category, Category::Config);
+    }
+
+    #[test]
+    fn round_trip_compares_every_element_field() {
+        let data = TableData {
+            elements: vec![Element {
+                id: 1,
+                attribute: vec![ElementAttribute { id: 2, value: 1 }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut report = PreflightReport::default();
+        check_round_trip(&data, round_trip(&data), &mut report);
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+
+        let mut changed = data.clone();
+        changed.elements[0].attribute[0].value = 5;
+        check_round_trip(&data, Ok(changed), &mut report);
+        let errors = errors(&report);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "json数据转换为配置表后内容不一致");
+    }
+}
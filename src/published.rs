@@ -0,0 +1,287 @@
+use crate::bean::ModData;
+use crate::data::{editor_dir, read_data, write_atomic};
+use crate::uploadscreen::MOD_DATA_FILE;
+use crate::workshop::{ItemId, PublishedItem, SessionStatus, WorkshopBackend};
+use crate::{Color32, Deserialize, MyApp, RichText, ScrollArea, Serialize, egui};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// 上传记录和备份一样保存在工作区之外，不会被下一次上传带上
+const UPLOAD_LOG_FILE: &str = "upload_log.json";
+
+/// 一次上传的记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadRecord {
+    pub time: String,
+    /// 创建物品失败时没有id
+    pub item_id: Option<ItemId>,
+    pub version: String,
+    pub change_note: String,
+    /// 物品已更新但没有完成的部分，如提交失败的其他语言
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// 失败原因，成功时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn upload_log_path(workspace: &Path) -> std::path::PathBuf {
+    editor_dir(workspace).join(UPLOAD_LOG_FILE)
+}
+
+pub fn read_upload_log(workspace: &Path) -> Result<Vec<UploadRecord>, String> {
+    let path = upload_log_path(workspace);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let json = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// 追加一条上传记录，版本号取自moddata.json
+pub fn record_upload(
+    workspace: &Path,
+    item_id: Option<ItemId>,
+    change_note: &str,
+    warnings: Vec<String>,
+    error: Option<String>,
+) -> Result<(), String> {
+    let mut log = read_upload_log(workspace)?;
+    let version = std::fs::read_to_string(workspace.join(MOD_DATA_FILE))
+        .ok()
+        .and_then(|x| serde_json::from_str::<ModData>(&x).ok())
+        .map(|x| x.version)
+        .unwrap_or_default();
+    log.push(UploadRecord {
+        time: chrono::Local::now().to_rfc3339(),
+        item_id,
+        version,
+        change_note: change_note.to_string(),
+        warnings,
+        error,
+    });
+    let path = upload_log_path(workspace);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(&log).map_err(|e| e.to_string())?;
+    write_atomic(&path, json.as_bytes()).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn format_time(time: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .map(|x| x.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| time.to_string())
+}
+
+type ItemsResult = Result<Vec<PublishedItem>, String>;
+
+/// 一个工作区的物品id和上传记录
+struct LocalMod {
+    index: usize,
+    path: String,
+    name: String,
+    mod_id: Option<ItemId>,
+    log: Result<Vec<UploadRecord>, String>,
+}
+
+impl LocalMod {
+    fn read(index: usize, path: &str) -> Self {
+        let mod_data = std::fs::read_to_string(Path::new(path).join(MOD_DATA_FILE))
+            .ok()
+            .and_then(|x| serde_json::from_str::<ModData>(&x).ok());
+        Self {
+            index,
+            path: path.to_string(),
+            name: mod_data
+                .as_ref()
+                .map(|x| x.name.clone())
+                .unwrap_or_default(),
+            mod_id: mod_data.and_then(|x| x.mod_id),
+            log: read_upload_log(Path::new(path)),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PublishedWindowState {
+    pub open: bool,
+    /// 后台查询的结果，查询中为None
+    items: Arc<Mutex<Option<ItemsResult>>>,
+    mods: Vec<LocalMod>,
+}
+
+impl PublishedWindowState {
+    fn refresh(&mut self, backend: Option<Arc<dyn WorkshopBackend>>, workspaces: &[String]) {
+        self.mods = workspaces
+            .iter()
+            .enumerate()
+            .map(|(i, path)| LocalMod::read(i, path))
+            .collect();
+        *self.items.lock().unwrap() = None;
+        if let Some(backend) = backend {
+            let items = self.items.clone();
+            backend.published_items(Box::new(move |result| {
+                *items.lock().unwrap() = Some(result);
+            }));
+        }
+    }
+}
+
+/// 连接创意工坊并查询已发布的物品
+pub fn open_published_window(app: &mut MyApp) {
+    let backend = match app.app_state.workshop.connect() {
+        Ok(backend) => Some(backend),
+        Err(e) => {
+            app.app_state.messages.push_back(e);
+            None
+        }
+    };
+    let state = &mut app.app_state.published_window;
+    state.refresh(backend, &app.app_data.workspaces);
+    state.open = true;
+}
+
+pub fn render_published_window(app: &mut MyApp, ctx: &egui::Context) {
+    let mut open = app.app_state.published_window.open;
+    if !open {
+        return;
+    }
+    let mut refresh = false;
+    let mut show = None;
+    let mut reopen = None;
+    egui::Window::new("我发布的物品")
+        .open(&mut open)
+        .default_size([560.0, 420.0])
+        .show(ctx, |ui| {
+            let state = &app.app_state.published_window;
+            let connected = app.app_state.workshop.backend().is_some();
+            ui.horizontal(|ui| {
+                match app.app_state.workshop.status() {
+                    status @ SessionStatus::Connected { .. } => {
+                        ui.label(format!("创意工坊: {}", status.describe()))
+                    }
+                    _ => ui.colored_label(Color32::RED, "没有连接创意工坊"),
+                };
+                refresh = ui.button("刷新").clicked();
+            });
+            ui.separator();
+
+            ScrollArea::vertical().show(ui, |ui| {
+                ui.strong("已发布的物品");
+                match &*state.items.lock().unwrap() {
+                    None if connected => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("查询中...");
+                        });
+                        ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                    }
+                    None => {}
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::RED, e);
+                    }
+                    Some(Ok(items)) if items.is_empty() => {
+                        ui.label("还没有发布过物品");
+                    }
+                    Some(Ok(items)) => {
+                        egui::Grid::new("published_items")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for item in items {
+                                    ui.label(item.id.to_string());
+                                    ui.label(&item.title);
+                                    ui.label(item.updated.map_or_else(String::new, |x| {
+                                        x.format("%Y-%m-%d %H:%M").to_string()
+                                    }));
+                                    match state.mods.iter().find(|x| x.mod_id == Some(item.id)) {
+                                        Some(local) => {
+                                            if ui
+                                                .link(&local.path)
+                                                .on_hover_text("打开这个工作区")
+                                                .clicked()
+                                            {
+                                                reopen = Some(local.index);
+                                            }
+                                        }
+                                        None => {
+                                            ui.weak("没有本地工作区");
+                                        }
+                                    }
+                                    if ui.button("查看").clicked() {
+                                        show = Some(item.id);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    }
+                }
+
+                ui.separator();
+                ui.strong("上传记录");
+                for local in &state.mods {
+                    let title = if local.name.is_empty() {
+                        local.path.clone()
+                    } else {
+                        format!("{} ({})", local.name, local.path)
+                    };
+                    egui::CollapsingHeader::new(title)
+                        .id_salt(("upload_log", local.index))
+                        .show(ui, |ui| match &local.log {
+                            Err(e) => {
+                                ui.colored_label(Color32::RED, e);
+                            }
+                            Ok(log) if log.is_empty() => {
+                                ui.label("没有上传记录");
+                            }
+                            Ok(log) => render_log(ui, local.index, log),
+                        });
+                }
+            });
+        });
+    app.app_state.published_window.open = open;
+
+    if refresh {
+        let backend = app.app_state.workshop.backend();
+        let workspaces = app.app_data.workspaces.clone();
+        app.app_state.published_window.refresh(backend, &workspaces);
+    }
+    if let Some(id) = show
+        && let Some(backend) = app.app_state.workshop.backend()
+    {
+        backend.show_item(id);
+    }
+    if let Some(index) = reopen {
+        if !app.app_state.dirty_tables.is_empty() {
+            app.app_state
+                .messages
+                .push_back("当前工作区有未保存的修改，请先保存".to_string());
+        } else {
+            app.app_data.current_workspace = index;
+            read_data(app);
+        }
+    }
+}
+
+fn render_log(ui: &mut egui::Ui, index: usize, log: &[UploadRecord]) {
+    egui::Grid::new(("upload_log_grid", index))
+        .striped(true)
+        .show(ui, |ui| {
+            for record in log.iter().rev() {
+                ui.label(format_time(&record.time));
+                ui.label(record.item_id.map_or_else(String::new, |x| x.to_string()));
+                ui.label(&record.version);
+                match &record.error {
+                    None if !record.warnings.is_empty() => ui
+                        .label(RichText::new("部分成功").color(Color32::YELLOW))
+                        .on_hover_text(record.warnings.join("\n")),
+                    None => ui.label(RichText::new("成功").color(Color32::GREEN)),
+                    Some(e) => ui
+                        .label(RichText::new("失败").color(Color32::RED))
+                        .on_hover_text(e),
+                };
+                ui.label(&record.change_note);
+                ui.end_row();
+            }
+        });
+}
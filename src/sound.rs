@@ -0,0 +1,600 @@
+use crate::bean::{AudioType, SoundEntry};
+use crate::data::write_atomic;
+use crate::page_render::libpage::next_id;
+use crate::{Color32, ComboBox, MyApp, RichText, ScrollArea, egui};
+use egui_extras::{Column, TableBuilder};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const SOUND_DIR: &str = "Sound_Mod";
+const REGISTRY_FILE: &str = "sound_mod.json";
+const SOUND_EXTENSIONS: [&str; 7] = ["wav", "aif", "aiff", "aac", "m4a", "ogg", "mp3"];
+
+/// 从文件头读到的音频信息，读不到的字段为None
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundInfo {
+    pub format: String,
+    pub duration: Option<f32>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub size: u64,
+}
+
+impl SoundInfo {
+    pub fn summary(&self) -> String {
+        let mut parts = vec![self.format.clone()];
+        if let Some(duration) = self.duration {
+            parts.push(format!("{:.2}s", duration));
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            parts.push(format!("{}Hz", sample_rate));
+        }
+        if let Some(channels) = self.channels {
+            parts.push(format!("{}ch", channels));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// 读取音频文件头，支持 wav、aiff 和 adts 格式的 aac，其他格式只识别扩展名
+pub fn read_sound_info(path: &Path) -> io::Result<SoundInfo> {
+    let bytes = fs::read(path)?;
+    let size = bytes.len() as u64;
+    let invalid = |format: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("无法解析{}文件头", format),
+        )
+    };
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WAVE") {
+        return parse_wav(&bytes, size).ok_or_else(|| invalid("WAV"));
+    }
+    if bytes.starts_with(b"FORM") && matches!(bytes.get(8..12), Some(b"AIFF") | Some(b"AIFC")) {
+        return parse_aiff(&bytes, size).ok_or_else(|| invalid("AIFF"));
+    }
+    let extension = path
+        .extension()
+        .and_then(|x| x.to_str())
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    if extension == "AAC" {
+        return parse_adts(&bytes, size).ok_or_else(|| invalid("AAC"));
+    }
+    Ok(SoundInfo {
+        format: extension,
+        duration: None,
+        sample_rate: None,
+        channels: None,
+        size,
+    })
+}
+
+/// 按扩展名推断游戏使用的音频类型
+pub fn audio_type_for(file: &str) -> AudioType {
+    let extension = Path::new(file)
+        .extension()
+        .and_then(|x| x.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "aif" | "aiff" => AudioType::Aiff,
+        "aac" | "m4a" => AudioType::Acc,
+        _ => AudioType::Unknown,
+    }
+}
+
+fn read_u16_le(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_u16_be(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32_be(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn parse_wav(bytes: &[u8], size: u64) -> Option<SoundInfo> {
+    let mut pos = 12;
+    let mut fmt = None;
+    let mut data_size = None;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let len = read_u32_le(bytes, pos + 4)? as usize;
+        let body = pos + 8;
+        match id {
+            b"fmt " => {
+                let channels = read_u16_le(bytes, body + 2)?;
+                let sample_rate = read_u32_le(bytes, body + 4)?;
+                let byte_rate = read_u32_le(bytes, body + 8)?;
+                fmt = Some((channels, sample_rate, byte_rate));
+            }
+            b"data" => data_size = Some(len.min(bytes.len() - body)),
+            _ => {}
+        }
+        // 块按偶数字节对齐
+        pos = body + len + (len & 1);
+    }
+    let (channels, sample_rate, byte_rate) = fmt?;
+    Some(SoundInfo {
+        format: "WAV".to_string(),
+        duration: data_size
+            .filter(|_| byte_rate > 0)
+            .map(|x| x as f32 / byte_rate as f32),
+        sample_rate: Some(sample_rate),
+        channels: Some(channels),
+        size,
+    })
+}
+
+fn parse_aiff(bytes: &[u8], size: u64) -> Option<SoundInfo> {
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let len = read_u32_be(bytes, pos + 4)? as usize;
+        let body = pos + 8;
+        if &bytes[pos..pos + 4] == b"COMM" {
+            let channels = read_u16_be(bytes, body)?;
+            let frames = read_u32_be(bytes, body + 2)?;
+            let sample_rate = extended_to_f64(bytes.get(body + 8..body + 18)?);
+            return Some(SoundInfo {
+                format: "AIFF".to_string(),
+                duration: (sample_rate > 0.0).then(|| (frames as f64 / sample_rate) as f32),
+                sample_rate: Some(sample_rate.round() as u32),
+                channels: Some(channels),
+                size,
+            });
+        }
+        pos = body + len + (len & 1);
+    }
+    None
+}
+
+/// aiff 中采样率使用的80位扩展精度浮点数
+fn extended_to_f64(bytes: &[u8]) -> f64 {
+    let exponent = (((bytes[0] & 0x7F) as i32) << 8) | bytes[1] as i32;
+    let mantissa = u64::from_be_bytes(bytes[2..10].try_into().unwrap());
+    let value = mantissa as f64 * 2f64.powi(exponent - 16383 - 63);
+    if bytes[0] & 0x80 != 0 { -value } else { value }
+}
+
+const ADTS_SAMPLE_RATES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+/// 逐帧读取 adts 头，每个原始数据块1024个采样
+fn parse_adts(bytes: &[u8], size: u64) -> Option<SoundInfo> {
+    let mut pos = 0;
+    // 跳过ID3标签
+    if bytes.starts_with(b"ID3") && bytes.len() >= 10 {
+        let tag = bytes[6..10]
+            .iter()
+            .fold(0usize, |acc, x| (acc << 7) | (*x & 0x7F) as usize);
+        pos = 10 + tag;
+    }
+    let mut samples = 0u64;
+    let mut header = None;
+    while pos + 7 <= bytes.len() {
+        let b = &bytes[pos..pos + 7];
+        if b[0] != 0xFF || b[1] & 0xF0 != 0xF0 {
+            break;
+        }
+        let sample_rate = *ADTS_SAMPLE_RATES.get(((b[2] >> 2) & 0x0F) as usize)?;
+        let channels = (((b[2] & 0x01) << 2) | (b[3] >> 6)) as u16;
+        let frame_len =
+            (((b[3] & 0x03) as usize) << 11) | ((b[4] as usize) << 3) | (b[5] >> 5) as usize;
+        if frame_len < 7 {
+            break;
+        }
+        header.get_or_insert((sample_rate, channels));
+        samples += 1024 * ((b[6] & 0x03) as u64 + 1);
+        pos += frame_len;
+    }
+    let (sample_rate, channels) = header?;
+    Some(SoundInfo {
+        format: "AAC".to_string(),
+        duration: Some(samples as f32 / sample_rate as f32),
+        sample_rate: Some(sample_rate),
+        channels: Some(channels),
+        size,
+    })
+}
+
+/// 当前模组 Sound_Mod 的音效注册表
+#[derive(Default)]
+pub struct SoundRegistry {
+    dir: Option<PathBuf>,
+    pub entries: Vec<SoundEntry>,
+    /// Sound_Mod 中的音频文件及其信息
+    pub files: Vec<(String, Result<SoundInfo, String>)>,
+}
+
+impl SoundRegistry {
+    /// 工作区变化时重新读取
+    pub fn sync(&mut self, workspace: Option<&str>) -> Option<String> {
+        let dir = workspace.map(|x| Path::new(x).join(SOUND_DIR));
+        if dir == self.dir {
+            return None;
+        }
+        self.dir = dir;
+        self.refresh().err()
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// 读取注册表并扫描 Sound_Mod 中的文件
+    pub fn refresh(&mut self) -> Result<(), String> {
+        self.entries.clear();
+        self.files.clear();
+        let Some(dir) = self.dir.clone() else {
+            return Ok(());
+        };
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let extension = path
+                    .extension()
+                    .and_then(|x| x.to_str())
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                if !path.is_file() || !SOUND_EXTENSIONS.contains(&extension.as_str()) {
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy().to_string();
+                let info = read_sound_info(&path).map_err(|e| e.to_string());
+                self.files.push((name, info));
+            }
+        }
+        self.files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let path = dir.join(REGISTRY_FILE);
+        if path.exists() {
+            let json =
+                fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            self.entries =
+                serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let Some(dir) = &self.dir else {
+            return Err("请先选择工作区".to_string());
+        };
+        let path = dir.join(REGISTRY_FILE);
+        let json = serde_json::to_string_pretty(&self.entries).map_err(|e| e.to_string())?;
+        fs::create_dir_all(dir)
+            .and_then(|_| write_atomic(&path, json.as_bytes()))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn find(&self, id: i32) -> Option<&SoundEntry> {
+        self.entries.iter().find(|x| x.id == id)
+    }
+
+    pub fn info(&self, file: &str) -> Option<&Result<SoundInfo, String>> {
+        self.files.iter().find(|x| x.0 == file).map(|x| &x.1)
+    }
+
+    /// 为文件分配新id，返回新行下标
+    pub fn register(&mut self, file: &str) -> usize {
+        self.entries.push(SoundEntry {
+            id: next_id(&self.entries),
+            file: file.to_string(),
+            audio_type: audio_type_for(file),
+        });
+        self.entries.len() - 1
+    }
+
+    /// 还没有注册的文件
+    pub fn unregistered(&self) -> Vec<&str> {
+        let registered: HashSet<&str> = self.entries.iter().map(|x| x.file.as_str()).collect();
+        self.files
+            .iter()
+            .map(|x| x.0.as_str())
+            .filter(|x| !registered.contains(x))
+            .collect()
+    }
+
+    /// 检查注册表本身：重复id、缺少文件、无法解析的文件和类型不符
+    pub fn validate(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        let mut ids = HashMap::new();
+        for entry in &self.entries {
+            if let Some(file) = ids.insert(entry.id, &entry.file) {
+                issues.push(format!(
+                    "音效id {} 重复: {} 和 {}",
+                    entry.id, file, entry.file
+                ));
+            }
+            match self.info(&entry.file) {
+                None => issues.push(format!("音效 {} 的文件 {} 不存在", entry.id, entry.file)),
+                Some(Err(e)) => {
+                    issues.push(format!("音效 {} 的文件 {}: {}", entry.id, entry.file, e))
+                }
+                Some(Ok(_)) => {
+                    let expected = audio_type_for(&entry.file);
+                    if expected != AudioType::Unknown && expected != entry.audio_type {
+                        issues.push(format!(
+                            "音效 {} 的类型为 {:?}，但文件 {} 是 {:?}",
+                            entry.id, entry.audio_type, entry.file, expected
+                        ));
+                    }
+                }
+            }
+        }
+        issues
+    }
+}
+
+#[derive(Default)]
+pub struct SoundWindowState {
+    pub open: bool,
+}
+
+pub fn render_sound_window(app: &mut MyApp, ctx: &egui::Context) {
+    let mut open = app.app_state.sound_window.open;
+    if !open {
+        return;
+    }
+    let mut refresh = false;
+    let mut save = false;
+    egui::Window::new("音效资源")
+        .open(&mut open)
+        .default_size([720.0, 440.0])
+        .show(ctx, |ui| {
+            let sounds = &mut app.app_state.sounds;
+            let Some(dir) = sounds.dir() else {
+                ui.label("请先选择工作区");
+                return;
+            };
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} · 已注册 {} / {} 个文件",
+                    dir.display(),
+                    sounds.entries.len(),
+                    sounds.files.len()
+                ));
+                refresh = ui.button("刷新").clicked();
+                save = ui.button("保存注册表").clicked();
+            });
+            for issue in sounds.validate() {
+                ui.colored_label(Color32::RED, issue);
+            }
+            ui.separator();
+
+            let mut remove = None;
+            let unregistered: Vec<String> = sounds
+                .unregistered()
+                .into_iter()
+                .map(str::to_string)
+                .collect();
+            let infos: Vec<String> = sounds
+                .entries
+                .iter()
+                .map(|x| match sounds.info(&x.file) {
+                    Some(Ok(info)) => info.summary(),
+                    Some(Err(e)) => e.clone(),
+                    None => "文件不存在".to_string(),
+                })
+                .collect();
+            TableBuilder::new(ui)
+                .id_salt("sound_registry")
+                .striped(true)
+                .resizable(true)
+                .max_scroll_height(240.0)
+                .column(Column::auto().at_least(70.0))
+                .column(Column::initial(180.0).clip(true))
+                .column(Column::auto().at_least(80.0))
+                .column(Column::remainder())
+                .column(Column::auto())
+                .header(20.0, |mut header| {
+                    for title in ["Id", "文件", "AudioType", "信息", ""] {
+                        header.col(|ui| {
+                            ui.strong(title);
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(22.0, sounds.entries.len(), |mut row| {
+                        let i = row.index();
+                        let entry = &mut sounds.entries[i];
+                        row.col(|ui| {
+                            ui.add(egui::DragValue::new(&mut entry.id));
+                        });
+                        row.col(|ui| {
+                            ui.label(&entry.file);
+                        });
+                        row.col(|ui| {
+                            ComboBox::from_id_salt(("sound_type", i))
+                                .selected_text(format!("{:?}", entry.audio_type))
+                                .show_ui(ui, |ui| {
+                                    for audio_type in
+                                        [AudioType::Unknown, AudioType::Acc, AudioType::Aiff]
+                                    {
+                                        ui.selectable_value(
+                                            &mut entry.audio_type,
+                                            audio_type,
+                                            format!("{:?}", audio_type),
+                                        );
+                                    }
+                                });
+                        });
+                        row.col(|ui| {
+                            ui.label(&infos[i]);
+                        });
+                        row.col(|ui| {
+                            if ui.small_button("×").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                    });
+                });
+            if let Some(i) = remove {
+                sounds.entries.remove(i);
+            }
+
+            ui.separator();
+            ui.strong(format!("未注册的文件 {}", unregistered.len()));
+            ScrollArea::vertical()
+                .id_salt("sound_unregistered")
+                .show(ui, |ui| {
+                    for file in &unregistered {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("注册").clicked() {
+                                sounds.register(file);
+                            }
+                            ui.label(file);
+                            if let Some(info) = sounds.info(file) {
+                                let text = match info {
+                                    Ok(info) => info.summary(),
+                                    Err(e) => e.clone(),
+                                };
+                                ui.label(RichText::new(text).weak());
+                            }
+                        });
+                    }
+                });
+        });
+    app.app_state.sound_window.open = open;
+
+    if save && let Err(e) = app.app_state.sounds.save() {
+        app.app_state.messages.push_back(e);
+    }
+    if refresh && let Err(e) = app.app_state.sounds.refresh() {
+        app.app_state.messages.push_back(e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    fn wav(sample_rate: u32, channels: u16, data_len: u32) -> Vec<u8> {
+        let byte_rate = sample_rate * channels as u32 * 2;
+        let mut bytes = Vec::new();
+        bytes.extend(b"RIFF");
+        bytes.extend((36 + data_len).to_le_bytes());
+        bytes.extend(b"WAVEfmt ");
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(channels.to_le_bytes());
+        bytes.extend(sample_rate.to_le_bytes());
+        bytes.extend(byte_rate.to_le_bytes());
+        bytes.extend((channels * 2).to_le_bytes());
+        bytes.extend(16u16.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend(data_len.to_le_bytes());
+        bytes.extend(vec![0; data_len as usize]);
+        bytes
+    }
+
+    fn aiff(frames: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(b"FORM");
+        bytes.extend(30u32.to_be_bytes());
+        bytes.extend(b"AIFFCOMM");
+        bytes.extend(18u32.to_be_bytes());
+        bytes.extend(1u16.to_be_bytes());
+        bytes.extend(frames.to_be_bytes());
+        bytes.extend(16u16.to_be_bytes());
+        // 44100 的80位扩展精度表示
+        bytes.extend([0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]);
+        bytes
+    }
+
+    fn adts(frames: usize) -> Vec<u8> {
+        // 44100Hz、双声道、每帧10字节
+        let frame = [0xFF, 0xF1, 0x50, 0x80, 0x01, 0x5F, 0xFC, 0, 0, 0];
+        frame.repeat(frames)
+    }
+
+    #[test]
+    fn reads_wav_duration() {
+        let dir = TempDir::new("sound");
+        let path = dir.join("hit.wav");
+        fs::write(&path, wav(8000, 1, 8000)).unwrap();
+        let info = read_sound_info(&path).unwrap();
+        assert_eq!(info.format, "WAV");
+        assert_eq!(info.duration, Some(0.5));
+        assert_eq!(info.sample_rate, Some(8000));
+    }
+
+    #[test]
+    fn reads_aiff_duration() {
+        let dir = TempDir::new("sound");
+        let path = dir.join("show.aiff");
+        fs::write(&path, aiff(44100)).unwrap();
+        let info = read_sound_info(&path).unwrap();
+        assert_eq!(info.format, "AIFF");
+        assert_eq!(info.duration, Some(1.0));
+        assert_eq!(info.sample_rate, Some(44100));
+    }
+
+    #[test]
+    fn reads_aac_duration_from_adts_frames() {
+        let dir = TempDir::new("sound");
+        let path = dir.join("bgm.aac");
+        fs::write(&path, adts(441)).unwrap();
+        let info = read_sound_info(&path).unwrap();
+        assert_eq!(info.format, "AAC");
+        assert_eq!(info.channels, Some(2));
+        assert!((info.duration.unwrap() - 441.0 * 1024.0 / 44100.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rejects_truncated_headers() {
+        let dir = TempDir::new("sound");
+        let path = dir.join("broken.wav");
+        fs::write(&path, b"RIFF\0\0\0\0WAVE").unwrap();
+        assert!(read_sound_info(&path).is_err());
+    }
+
+    /// 有两个音效文件的工作区，已登记a.aiff和不存在的missing.aac
+    fn registered_workspace() -> (TempDir, SoundRegistry) {
+        let root = TempDir::new("registry");
+        let dir = root.join(SOUND_DIR);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.aiff"), aiff(100)).unwrap();
+        fs::write(dir.join("b.wav"), wav(8000, 1, 16)).unwrap();
+
+        let mut registry = SoundRegistry::default();
+        assert_eq!(registry.sync(root.path().to_str()), None);
+        assert_eq!(registry.unregistered(), vec!["a.aiff", "b.wav"]);
+        let a = registry.register("a.aiff");
+        assert_eq!(registry.entries[a].audio_type, AudioType::Aiff);
+        registry.register("missing.aac");
+        registry.save().unwrap();
+        (root, registry)
+    }
+
+    #[test]
+    fn registry_round_trips_through_json() {
+        let (root, registry) = registered_workspace();
+        let mut loaded = SoundRegistry::default();
+        loaded.sync(root.path().to_str());
+        assert_eq!(loaded.entries, registry.entries);
+        assert_eq!(loaded.unregistered(), vec!["b.wav"]);
+    }
+
+    #[test]
+    fn registry_validation_reports_missing_files_and_conflicts() {
+        let (root, _) = registered_workspace();
+        let mut loaded = SoundRegistry::default();
+        loaded.sync(root.path().to_str());
+        assert_eq!(loaded.validate(), vec!["音效 2 的文件 missing.aac 不存在"]);
+
+        loaded.entries[0].audio_type = AudioType::Acc;
+        loaded.entries[1].id = 1;
+        assert_eq!(loaded.validate().len(), 3);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::bean::Localization;
+use crate::data::{TableData, write_luban_bytes};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 测试用的临时目录，离开作用域时删除，断言失败也不会留下文件
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "rhe_{}_{}_{}",
+            name,
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        // 上次运行被强制结束时可能留下同名目录
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 写入能通过上传前检查的模组文件夹
+pub fn write_mod_folder(folder: &Path) {
+    std::fs::create_dir_all(folder).unwrap();
+    std::fs::write(
+        folder.join("moddata.json"),
+        r#"{"ModName": "猫猫", "ModDesc": "描述", "Version": "1.0.0"}"#,
+    )
+    .unwrap();
+    let data = TableData {
+        l10n: vec![Localization {
+            id: 1,
+            cn: "猫".to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    write_luban_bytes(&folder.join("Config_Mod"), &data).unwrap();
+}
@@ -0,0 +1,234 @@
+use crate::data::write_atomic;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use std::path::{Path, PathBuf};
+
+/// 创意工坊预览图的大小上限
+pub const MAX_PREVIEW_BYTES: u64 = 1024 * 1024;
+/// 边长小于这个值时在创意工坊页面上会显得模糊
+pub const MIN_PREVIEW_SIDE: u32 = 256;
+/// 生成的预览图文件名的后缀
+const PREVIEW_SUFFIX: &str = "_preview.jpg";
+/// 生成预览图时可选的宽度
+pub const PREVIEW_WIDTHS: [u32; 3] = [512, 768, 1024];
+
+/// 裁剪的比例
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Aspect {
+    /// 创意工坊列表中的方形缩略图
+    #[default]
+    Square,
+    /// 物品页面的宽幅展示图
+    Wide,
+}
+
+impl Aspect {
+    pub const ALL: [Aspect; 2] = [Aspect::Square, Aspect::Wide];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aspect::Square => "1:1",
+            Aspect::Wide => "16:9",
+        }
+    }
+
+    /// 按宽度计算的尺寸
+    pub fn size(&self, width: u32) -> (u32, u32) {
+        match self {
+            Aspect::Square => (width, width),
+            Aspect::Wide => (width, width * 9 / 16),
+        }
+    }
+}
+
+/// 预览图文件的格式、尺寸和大小
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThumbnailInfo {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub size: u64,
+}
+
+impl ThumbnailInfo {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} {}x{} {}KB",
+            format_name(self.format),
+            self.width,
+            self.height,
+            self.size.div_ceil(1024)
+        )
+    }
+
+    /// 不符合创意工坊要求的地方，有错误时不能上传
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !matches!(self.format, ImageFormat::Png | ImageFormat::Jpeg) {
+            errors.push(format!(
+                "不支持{}格式，请使用PNG或JPG",
+                format_name(self.format)
+            ));
+        }
+        if self.size > MAX_PREVIEW_BYTES {
+            errors.push(format!(
+                "文件大小 {}KB 超过 {}KB",
+                self.size.div_ceil(1024),
+                MAX_PREVIEW_BYTES / 1024
+            ));
+        }
+        errors
+    }
+
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.width.min(self.height) < MIN_PREVIEW_SIDE {
+            warnings.push(format!(
+                "尺寸小于 {}x{}，显示时会模糊",
+                MIN_PREVIEW_SIDE, MIN_PREVIEW_SIDE
+            ));
+        }
+        let matches_aspect = Aspect::ALL.iter().any(|x| {
+            let (w, h) = x.size(self.width);
+            w.abs_diff(self.width) <= 1 && h.abs_diff(self.height) <= 1
+        });
+        if !matches_aspect {
+            warnings.push("不是1:1或16:9的比例，创意工坊会裁掉一部分".to_string());
+        }
+        warnings
+    }
+}
+
+fn format_name(format: ImageFormat) -> String {
+    format
+        .extensions_str()
+        .first()
+        .map_or_else(|| format!("{:?}", format), |x| x.to_uppercase())
+}
+
+/// 预览图写在模组文件夹旁边，不会被当作模组内容上传
+pub fn preview_path(folder: &Path) -> PathBuf {
+    let mut name = folder.file_name().unwrap_or_default().to_os_string();
+    name.push(PREVIEW_SUFFIX);
+    folder.with_file_name(name)
+}
+
+/// 读取并解码图片，失败时返回原因
+pub fn load(path: &Path) -> Result<(ThumbnailInfo, DynamicImage), String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let format = image::guess_format(&bytes).map_err(|e| format!("无法识别图片格式: {}", e))?;
+    let image = image::load_from_memory_with_format(&bytes, format)
+        .map_err(|e| format!("图片解码失败: {}", e))?;
+    let info = ThumbnailInfo {
+        format,
+        width: image.width(),
+        height: image.height(),
+        size: bytes.len() as u64,
+    };
+    Ok((info, image))
+}
+
+/// 居中裁剪到指定比例并缩放，编码为不超过大小上限的jpg
+pub fn make_preview(
+    image: &DynamicImage,
+    aspect: Aspect,
+    width: u32,
+    out: &Path,
+) -> Result<ThumbnailInfo, String> {
+    let (width, height) = aspect.size(width);
+    let preview = crop_to_aspect(image, width, height)
+        .resize_exact(width, height, FilterType::Lanczos3)
+        .to_rgb8();
+    // 从高质量开始尝试，直到文件足够小
+    for quality in [90, 80, 70, 60] {
+        let mut bytes = Vec::new();
+        JpegEncoder::new_with_quality(&mut bytes, quality)
+            .encode_image(&preview)
+            .map_err(|e| format!("图片编码失败: {}", e))?;
+        if bytes.len() as u64 <= MAX_PREVIEW_BYTES {
+            write_atomic(out, &bytes).map_err(|e| format!("{}: {}", out.display(), e))?;
+            return Ok(ThumbnailInfo {
+                format: ImageFormat::Jpeg,
+                width,
+                height,
+                size: bytes.len() as u64,
+            });
+        }
+    }
+    Err("压缩后仍然超过大小上限，请选择更小的尺寸".to_string())
+}
+
+/// 按比例能放下的最大居中区域
+fn crop_to_aspect(image: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    let (w, h) = (image.width() as u64, image.height() as u64);
+    let (crop_w, crop_h) = if w * height as u64 > h * width as u64 {
+        (h * width as u64 / height as u64, h)
+    } else {
+        (w, w * height as u64 / width as u64)
+    };
+    let (crop_w, crop_h) = (crop_w.max(1) as u32, crop_h.max(1) as u32);
+    image.crop_imm(
+        (image.width() - crop_w) / 2,
+        (image.height() - crop_h) / 2,
+        crop_w,
+        crop_h,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use image::RgbImage;
+    use std::fs;
+
+    #[test]
+    fn reports_decode_errors() {
+        let root = TempDir::new("thumbnail");
+        fs::write(root.join("broken.png"), b"not an image").unwrap();
+        assert!(
+            load(&root.join("broken.png"))
+                .unwrap_err()
+                .contains("图片格式")
+        );
+    }
+
+    #[test]
+    fn warns_about_small_images_with_other_aspects() {
+        let root = TempDir::new("thumbnail");
+        let source = root.join("wide.png");
+        RgbImage::new(400, 200).save(&source).unwrap();
+        let (info, _) = load(&source).unwrap();
+        assert_eq!(
+            (info.format, info.width, info.height),
+            (ImageFormat::Png, 400, 200)
+        );
+        assert!(info.errors().is_empty());
+        assert_eq!(info.warnings().len(), 2);
+    }
+
+    #[test]
+    fn writes_cropped_previews_within_limits() {
+        let root = TempDir::new("thumbnail");
+        let image = RgbImage::from_fn(400, 200, |x, _| image::Rgb([(x % 256) as u8, 0, 0]));
+        let out = preview_path(&root.join("mod"));
+        assert_eq!(out, root.join("mod_preview.jpg"));
+        let preview =
+            make_preview(&DynamicImage::ImageRgb8(image), Aspect::Square, 512, &out).unwrap();
+        assert_eq!(load(&out).unwrap().0, preview);
+        assert_eq!((preview.width, preview.height), (512, 512));
+        assert!(preview.errors().is_empty() && preview.warnings().is_empty());
+    }
+
+    #[test]
+    fn rejects_files_over_size_limit() {
+        let info = ThumbnailInfo {
+            format: ImageFormat::Jpeg,
+            width: 512,
+            height: 512,
+            size: MAX_PREVIEW_BYTES + 1,
+        };
+        assert_eq!(info.errors().len(), 1);
+    }
+}
@@ -0,0 +1,405 @@
+use crate::preflight::check_mod_folder;
+use crate::published::record_upload;
+use crate::uploadscreen::save_mod_id;
+use crate::workshop::{ItemId, ItemUpdate, ItemUpdated, UpdatePhase, UpdateWatch, WorkshopBackend};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+/// 查询进度和取消标记的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 上传的状态，由后台线程通过channel发送
+#[derive(Debug, Clone, PartialEq)]
+pub enum UploadState {
+    Validating,
+    Creating,
+    Preparing,
+    Uploading {
+        bytes: u64,
+        total: u64,
+    },
+    Committing,
+    /// `warnings` 为提交失败的其他语言，物品本身已经更新
+    Done {
+        id: ItemId,
+        warnings: Vec<String>,
+    },
+    Failed {
+        err: String,
+    },
+}
+
+impl UploadState {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, UploadState::Done { .. } | UploadState::Failed { .. })
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            UploadState::Validating => "检查MOD内容中".to_string(),
+            UploadState::Creating => "创建创意工坊物品中".to_string(),
+            UploadState::Preparing => "准备内容中".to_string(),
+            UploadState::Uploading { bytes, total } => match self.fraction() {
+                Some(fraction) => format!(
+                    "上传中: {:.1} / {:.1} MB ({:.0}%)",
+                    *bytes as f64 / 1_048_576.0,
+                    *total as f64 / 1_048_576.0,
+                    fraction * 100.0
+                ),
+                None => "上传中".to_string(),
+            },
+            UploadState::Committing => "提交修改中".to_string(),
+            UploadState::Done { id, warnings } if warnings.is_empty() => {
+                format!("上传创意工坊物品成功，物品id: {}", id)
+            }
+            UploadState::Done { id, warnings } => format!(
+                "上传创意工坊物品成功，物品id: {}，以下语言提交失败: {}",
+                id,
+                warnings.join("；")
+            ),
+            UploadState::Failed { err } => format!("上传失败: {}", err),
+        }
+    }
+
+    /// 上传的进度，总量未知时为None
+    pub fn fraction(&self) -> Option<f32> {
+        match self {
+            UploadState::Uploading { bytes, total } if *total > 0 => {
+                Some((*bytes as f64 / *total as f64).clamp(0.0, 1.0) as f32)
+            }
+            UploadState::Done { .. } => Some(1.0),
+            _ => None,
+        }
+    }
+}
+
+/// 在后台线程中进行的一次上传
+pub struct UploadTask {
+    receiver: Receiver<UploadState>,
+    cancelled: Arc<AtomicBool>,
+    pub state: UploadState,
+}
+
+impl UploadTask {
+    /// 开始上传，没有物品id时先创建物品
+    pub fn start(
+        backend: Arc<dyn WorkshopBackend>,
+        update: ItemUpdate,
+        item_id: Option<ItemId>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let worker = Worker {
+            backend,
+            sender,
+            cancelled: cancelled.clone(),
+        };
+        std::thread::spawn(move || worker.run(update, item_id));
+        Self {
+            receiver,
+            cancelled,
+            state: UploadState::Validating,
+        }
+    }
+
+    /// 读取后台线程发送的状态，返回是否有变化
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(state) = self.receiver.try_recv() {
+            self.state = state;
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelling(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) && !self.state.is_finished()
+    }
+}
+
+struct Worker {
+    backend: Arc<dyn WorkshopBackend>,
+    sender: Sender<UploadState>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Worker {
+    fn send(&self, state: UploadState) {
+        // 界面已经不再接收时忽略
+        let _ = self.sender.send(state);
+    }
+
+    fn check_cancelled(&self) -> Result<(), String> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            Err("已取消".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn run(self, update: ItemUpdate, item_id: Option<ItemId>) {
+        let mut created = None;
+        let result = self.upload(update.clone(), item_id, &mut created);
+        let (id, warnings, error) = match &result {
+            Ok(x) => (Some(x.id), x.warnings.clone(), None),
+            Err(e) => (item_id.or(created), Vec::new(), Some(e.clone())),
+        };
+        if let Err(e) = record_upload(
+            &update.content_path,
+            id,
+            &update.change_note,
+            warnings,
+            error,
+        ) {
+            log::warn!("没有写入上传记录: {}", e);
+        }
+        match result {
+            Ok(ItemUpdated { id, warnings }) => {
+                self.send(UploadState::Done { id, warnings });
+                self.backend.show_item(id);
+            }
+            Err(err) => self.send(UploadState::Failed { err }),
+        }
+    }
+
+    fn upload(
+        &self,
+        mut update: ItemUpdate,
+        item_id: Option<ItemId>,
+        created: &mut Option<ItemId>,
+    ) -> Result<ItemUpdated, String> {
+        self.send(UploadState::Validating);
+        let errors = update.validate();
+        if !errors.is_empty() {
+            return Err(errors.join("；"));
+        }
+        // 确认之后文件可能又被修改过
+        let report = check_mod_folder(&update.content_path);
+        if report.has_errors() {
+            return Err(format!("上传前检查未通过: {}", report.summary()));
+        }
+        self.check_cancelled()?;
+
+        let id = match item_id {
+            Some(id) => id,
+            None => {
+                self.send(UploadState::Creating);
+                let (sender, receiver) = mpsc::channel();
+                self.backend.create_item(Box::new(move |result| {
+                    let _ = sender.send(result);
+                }));
+                // 请求发出后物品总会被创建，取消也要等到结果并保存id
+                let id = receiver
+                    .recv()
+                    .map_err(|_| "后端没有返回结果".to_string())?
+                    .map_err(|e| format!("创建创意工坊物品失败: {}", e))?;
+                *created = Some(id);
+                // 立即保存id，之后上传失败也不会重复创建物品
+                if let Err(e) = save_mod_id(&update.content_path, id) {
+                    log::warn!("没有保存物品id: {}", e);
+                }
+                self.check_cancelled()?;
+                id
+            }
+        };
+
+        update.metadata = serde_json::json!({ "ModId": id }).to_string();
+        let (sender, receiver) = mpsc::channel();
+        let watch = self.backend.update_item(
+            id,
+            &update,
+            Box::new(move |result| {
+                let _ = sender.send(result);
+            }),
+        );
+        let updated = self.wait(&receiver, watch.as_ref())??;
+        if let Err(e) = save_mod_id(&update.content_path, updated.id) {
+            log::warn!("没有保存物品id: {}", e);
+        }
+        Ok(updated)
+    }
+
+    /// 等待后端的回调，同时发送进度并检查是否取消
+    fn wait<T>(&self, receiver: &Receiver<T>, watch: &dyn UpdateWatch) -> Result<T, String> {
+        let mut last = None;
+        loop {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(result) => return Ok(result),
+                Err(RecvTimeoutError::Disconnected) => return Err("后端没有返回结果".to_string()),
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            if self.cancelled.load(Ordering::Relaxed) {
+                return if watch.cancel() {
+                    Err("已取消".to_string())
+                } else {
+                    Err("已停止等待，已提交的内容可能仍会完成上传".to_string())
+                };
+            }
+            let progress = watch.progress();
+            let state = match progress.phase {
+                UpdatePhase::Invalid => continue,
+                UpdatePhase::PreparingConfig | UpdatePhase::PreparingContent => {
+                    UploadState::Preparing
+                }
+                UpdatePhase::UploadingContent | UpdatePhase::UploadingPreview => {
+                    UploadState::Uploading {
+                        bytes: progress.uploaded,
+                        total: progress.total,
+                    }
+                }
+                UpdatePhase::Committing => UploadState::Committing,
+            };
+            if last.as_ref() != Some(&state) {
+                self.send(state.clone());
+                last = Some(state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{TempDir, write_mod_folder};
+    use crate::workshop::{ItemCallback, ItemsCallback, LocalBackend, UpdateCallback};
+
+    /// 本地后端，可以在创建物品时取消上传，或让其他语言提交失败
+    struct TestBackend {
+        inner: LocalBackend,
+        cancel_on_create: Option<Arc<AtomicBool>>,
+        failed_languages: Vec<String>,
+    }
+
+    impl WorkshopBackend for TestBackend {
+        fn name(&self) -> &'static str {
+            self.inner.name()
+        }
+
+        fn user_name(&self) -> String {
+            self.inner.user_name()
+        }
+
+        fn create_item(&self, done: ItemCallback) {
+            if let Some(cancelled) = &self.cancel_on_create {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+            self.inner.create_item(done);
+        }
+
+        fn update_item(
+            &self,
+            id: ItemId,
+            update: &ItemUpdate,
+            done: UpdateCallback,
+        ) -> Box<dyn UpdateWatch> {
+            let failed = self.failed_languages.clone();
+            self.inner.update_item(
+                id,
+                update,
+                Box::new(move |result| {
+                    done(result.map(|x| ItemUpdated {
+                        warnings: failed,
+                        ..x
+                    }))
+                }),
+            )
+        }
+
+        fn show_item(&self, id: ItemId) {
+            self.inner.show_item(id);
+        }
+
+        fn published_items(&self, done: ItemsCallback) {
+            self.inner.published_items(done);
+        }
+    }
+
+    /// 用测试后端上传一个模组文件夹，返回最后的状态
+    fn run(
+        folder: &std::path::Path,
+        cancel_on_create: bool,
+        failed_languages: Vec<String>,
+    ) -> Option<UploadState> {
+        write_mod_folder(folder);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let backend = TestBackend {
+            inner: LocalBackend::new(folder.with_file_name("workshop")),
+            cancel_on_create: cancel_on_create.then(|| cancelled.clone()),
+            failed_languages,
+        };
+        let (sender, receiver) = mpsc::channel();
+        let worker = Worker {
+            backend: Arc::new(backend),
+            sender,
+            cancelled,
+        };
+        let update = ItemUpdate {
+            title: "猫猫".to_string(),
+            content_path: folder.to_path_buf(),
+            ..Default::default()
+        };
+        worker.run(update, None);
+        receiver.try_iter().last()
+    }
+
+    #[test]
+    fn cancelling_while_creating_keeps_the_created_id() {
+        let root = TempDir::new("upload_task");
+        let folder = root.join("mod");
+        let last = run(&folder, true, Vec::new());
+        assert_eq!(
+            last,
+            Some(UploadState::Failed {
+                err: "已取消".to_string()
+            })
+        );
+        let mod_data = std::fs::read_to_string(folder.join("moddata.json")).unwrap();
+        assert!(mod_data.contains(r#""ModId": 1"#), "{}", mod_data);
+        let log = crate::published::read_upload_log(&folder).unwrap();
+        assert_eq!(log[0].item_id, Some(1));
+    }
+
+    #[test]
+    fn failed_translations_are_warnings() {
+        let root = TempDir::new("upload_task");
+        let folder = root.join("mod");
+        let warnings = vec!["english: 超时".to_string()];
+        let last = run(&folder, false, warnings.clone());
+        assert_eq!(
+            last,
+            Some(UploadState::Done {
+                id: 1,
+                warnings: warnings.clone()
+            })
+        );
+        let log = crate::published::read_upload_log(&folder).unwrap();
+        assert_eq!(log[0].item_id, Some(1));
+        assert_eq!((&log[0].warnings, &log[0].error), (&warnings, &None));
+    }
+
+    #[test]
+    fn progress_is_never_nan_and_is_rounded() {
+        let empty = UploadState::Uploading { bytes: 0, total: 0 };
+        assert_eq!(empty.fraction(), None);
+        assert_eq!(empty.describe(), "上传中");
+
+        let state = UploadState::Uploading { bytes: 1, total: 3 };
+        assert_eq!(state.describe(), "上传中: 0.0 / 0.0 MB (33%)");
+        assert_eq!(
+            UploadState::Uploading { bytes: 5, total: 3 }.fraction(),
+            Some(1.0)
+        );
+        let done = UploadState::Done {
+            id: 1,
+            warnings: Vec::new(),
+        };
+        assert!(done.is_finished());
+        assert!(!state.is_finished());
+    }
+}
@@ -1,11 +1,35 @@
 use eframe::egui::*;
-use std::path::Path;
-use std::sync::{Arc, Mutex, RwLock};
-// 注意：实际使用时需要根据Steam SDK的文档来导入正确的模块
-// 这里假设Steam SDK已经通过steamworks crate集成
-use steamworks::*;
-
-const APP_ID: u32 = 3371510;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::bean::ModData;
+use crate::data::{TableData, write_atomic};
+use crate::preflight::{PreflightReport, check_mod_folder, render_report};
+use crate::thumbnail::{self, Aspect, PREVIEW_WIDTHS, ThumbnailInfo, preview_path};
+use crate::upload::{UploadState, UploadTask};
+use crate::workshop::{
+    ItemId, ItemTranslation, ItemUpdate, LANGUAGES, SessionStatus, Visibility, WorkshopBackend,
+    WorkshopSession, language_name,
+};
+
+pub const MOD_DATA_FILE: &str = "moddata.json";
+
+/// 按模组修改的表生成的创意工坊标签
+pub const TABLE_TAGS: [&str; 4] = ["Units", "Relics", "Enemies", "Races"];
+
+/// 模组中有数据的表对应的标签
+pub fn table_tags(data: &TableData) -> Vec<String> {
+    [
+        !data.elements.is_empty(),
+        !data.relics.is_empty(),
+        !data.enemies.is_empty(),
+        !data.race_attributes.is_empty(),
+    ]
+    .into_iter()
+    .zip(TABLE_TAGS)
+    .filter(|x| x.0)
+    .map(|x| x.1.to_string())
+    .collect()
+}
 
 pub struct UploadScreen {
     // 是否为新MOD
@@ -18,26 +42,32 @@ pub struct UploadScreen {
     pub thumbnail_path: String,
     pub folder_path: String,
 
+    // 创意工坊选项
+    pub tags: Vec<String>,
+    pub extra_tags: String,
+    pub visibility: Visibility,
+    pub change_note: String,
+    pub translations: Vec<ItemTranslation>,
+
     // UI状态
     pub show_confirm_dialog: bool,
+    // 上一次上传前检查的结果
+    preflight: Option<PreflightReport>,
 
     // 上传状态
-    upload_progress: Arc<RwLock<UploadProgress>>,
+    status_text: String,
+    // 正在进行或最近一次的上传
+    task: Option<UploadTask>,
 
     // 缩略图纹理（用于显示）
     thumbnail_texture: Option<TextureHandle>,
+    // 缩略图的信息或解码失败的原因
+    thumbnail_info: Option<Result<ThumbnailInfo, String>>,
+    crop_aspect: Aspect,
+    crop_width: u32,
 
-    // Steam SDK初始化状态
-    steam_initialized: bool,
-    steam_error: Option<String>,
-
-    steam_client: Option<Client>,
-}
-
-struct UploadProgress {
-    uploading: bool,
-    progress: Option<UpdateWatchHandle>,
-    status_text: String,
+    // 从全局连接取得的创意工坊后端
+    backend: Option<Arc<dyn WorkshopBackend>>,
 }
 
 impl Default for UploadScreen {
@@ -49,54 +79,54 @@ impl Default for UploadScreen {
             mod_id: String::new(),
             thumbnail_path: String::new(),
             folder_path: String::new(),
+            tags: Vec::new(),
+            extra_tags: String::new(),
+            visibility: Visibility::default(),
+            change_note: String::new(),
+            translations: Vec::new(),
             show_confirm_dialog: false,
-            upload_progress: Arc::new(RwLock::new(UploadProgress {
-                uploading: false,
-                progress: None,
-                status_text: "准备上传".to_string(),
-            })),
+            preflight: None,
+            status_text: "准备上传".to_string(),
+            task: None,
             thumbnail_texture: None,
-            steam_initialized: false,
-            steam_error: None,
-            steam_client: None,
+            thumbnail_info: None,
+            crop_aspect: Aspect::default(),
+            crop_width: PREVIEW_WIDTHS[0],
+            backend: None,
         }
     }
 }
 
 impl UploadScreen {
-    pub fn ui(&mut self, ctx: &Context) -> bool {
-        let mut close_window = false;
+    pub fn ui(&mut self, ctx: &Context, session: &mut WorkshopSession) -> bool {
+        let mut open = true;
 
-        // 当界面第一次显示时初始化Steam SDK
-        if !self.steam_initialized && self.steam_error.is_none() {
-            self.initialize_steam();
+        // 使用全局的创意工坊连接
+        if self.backend.is_none() {
+            self.backend = session.backend();
         }
+        let mut reconnect = false;
 
-        // 创建模态窗口
-        let modal = Modal::new("upload_screen_modal".into()).show(ctx, |ui| {
-            ui.set_width(500.0);
-            ui.set_height(400.0);
+        self.poll(ctx);
 
+        // 不阻塞编辑器的窗口，上传时可以继续编辑
+        let window = Window::new("《轮盘英雄》 创意工坊上传工具")
+            .open(&mut open)
+            .default_width(520.0);
+        window.show(ctx, |ui| {
             ui.vertical(|ui| {
-                ui.horizontal(|ui| {
-                    ui.heading("《轮盘英雄》 创意工坊上传工具");
-
-                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                        if ui.button("❌").clicked() {
-                            close_window = true;
-                        }
-                    });
-                });
-
                 // 显示Steam状态
                 ui.horizontal(|ui| {
                     ui.label(RichText::new("Steam状态:").strong());
-                    if self.steam_initialized {
-                        ui.label(RichText::new("已连接").color(Color32::GREEN));
-                    } else if let Some(error) = &self.steam_error {
-                        ui.label(RichText::new(error).color(Color32::RED));
-                    } else {
-                        ui.label(RichText::new("初始化中...").color(Color32::YELLOW));
+                    let status = session.status();
+                    let color = match status {
+                        SessionStatus::Connected { .. } => Color32::GREEN,
+                        SessionStatus::Failed(_) => Color32::RED,
+                        SessionStatus::NotConnected => Color32::YELLOW,
+                    };
+                    ui.label(RichText::new(status.describe()).color(color));
+                    if !matches!(status, SessionStatus::Connected { .. }) {
+                        reconnect = ui.button("重新连接").clicked();
                     }
                 });
 
@@ -132,7 +162,12 @@ impl UploadScreen {
                 // 缩略图选择
                 ui.horizontal(|ui| {
                     ui.label("缩略图:");
-                    ui.text_edit_singleline(&mut self.thumbnail_path);
+                    if ui
+                        .text_edit_singleline(&mut self.thumbnail_path)
+                        .lost_focus()
+                    {
+                        self.load_thumbnail_texture(ui);
+                    }
                     if ui.button("浏览...").clicked() {
                         self.browse_thumbnail(ui);
                     }
@@ -145,6 +180,7 @@ impl UploadScreen {
                         ui.add(Image::new(texture).max_width(100.0));
                     });
                 }
+                self.thumbnail_info_ui(ui);
 
                 // 文件夹选择
                 ui.horizontal(|ui| {
@@ -155,45 +191,81 @@ impl UploadScreen {
                     }
                 });
 
+                CollapsingHeader::new("发布选项").show(ui, |ui| {
+                    ScrollArea::vertical().max_height(160.0).show(ui, |ui| {
+                        self.publish_options_ui(ui);
+                    });
+                });
+
                 ui.separator();
 
                 // 上传进度和状态
-                let upload_progress = self.upload_progress.clone();
-                ui.label(&upload_progress.read().unwrap().status_text);
-                let mut progress = 0.0;
-                let mut upload_message = "".to_string();
-                if let Some(handle) = &upload_progress.read().unwrap().progress {
-                    let (status, uploaded, total) = handle.progress();
-                    progress = uploaded as f32 / total as f32;
-                    upload_message = match status {
-                        UpdateStatus::Invalid => "无效操作",
-                        UpdateStatus::PreparingConfig => "准备配置中",
-                        UpdateStatus::PreparingContent => "准备内容中",
-                        UpdateStatus::UploadingContent => "上传内容中",
-                        UpdateStatus::UploadingPreviewFile => "上传预览文件中",
-                        UpdateStatus::CommittingChanges => "提交修改中",
+                ui.label(&self.status_text);
+                if let Some(report) = &self.preflight {
+                    CollapsingHeader::new(format!("上传前检查 ({})", report.summary()))
+                        .id_salt("upload_preflight")
+                        .default_open(report.has_errors())
+                        .show(ui, |ui| {
+                            ScrollArea::vertical()
+                                .id_salt("upload_preflight_scroll")
+                                .max_height(120.0)
+                                .show(ui, |ui| render_report(ui, report));
+                        });
+                }
+                let mut cancel = false;
+                if let Some(task) = &self.task {
+                    let state = &task.state;
+                    let color = match state {
+                        UploadState::Done { warnings, .. } if !warnings.is_empty() => {
+                            Color32::YELLOW
+                        }
+                        UploadState::Done { .. } => Color32::GREEN,
+                        UploadState::Failed { .. } => Color32::RED,
+                        _ => ui.visuals().text_color(),
+                    };
+                    ui.label(RichText::new(state.describe()).color(color));
+                    match state.fraction() {
+                        Some(fraction) => {
+                            ui.add(
+                                ProgressBar::new(fraction)
+                                    .text(format!("{:.0}%", fraction * 100.0)),
+                            );
+                        }
+                        None if !state.is_finished() => {
+                            ui.add(ProgressBar::new(0.0).animate(true));
+                        }
+                        None => {}
+                    }
+                    if !state.is_finished() {
+                        let text = if task.is_cancelling() {
+                            "取消中..."
+                        } else {
+                            "取消上传"
+                        };
+                        cancel = ui
+                            .add_enabled(!task.is_cancelling(), Button::new(text))
+                            .clicked();
                     }
-                    .to_string();
                 }
-                ui.add(ProgressBar::new(progress));
-                if progress > 0.0 {
-                    ui.label(format!("{}: {}%", upload_message, progress * 100.0));
+                if cancel && let Some(task) = &self.task {
+                    task.cancel();
                 }
 
                 ui.separator();
 
                 // 上传按钮 - 放在界面正下方
                 ui.vertical_centered(|ui| {
-                    ui.add_enabled_ui(self.steam_initialized, |ui| {
+                    ui.add_enabled_ui(self.backend.is_some(), |ui| {
                         if ui.button("测试连接").clicked() {
-                            let message = self.test_steam_connection();
-                            // 显示测试结果消息（在实际应用中可能需要添加一个消息显示区域）
-                            self.upload_progress.write().unwrap().status_text = message;
+                            self.status_text = self.test_steam_connection();
                         }
 
                         ui.add_space(10.0); // 添加一些间距
 
-                        if ui.button("上传").clicked() {
+                        if ui
+                            .add_enabled(!self.is_uploading(), Button::new("上传"))
+                            .clicked()
+                        {
                             self.validate_and_upload();
                         }
                     });
@@ -206,38 +278,127 @@ impl UploadScreen {
             });
         });
 
-        // 处理ESC键关闭
-        if modal.should_close() {
-            close_window = true;
+        if reconnect {
+            self.connect(session);
+        }
+
+        // 上传时关闭窗口不会停止上传，重新打开后继续显示进度
+        !open
+    }
+
+    /// 更新后台上传的状态，窗口关闭时也要调用
+    pub fn poll(&mut self, ctx: &Context) {
+        if let Some(task) = &mut self.task {
+            if task.poll()
+                && let UploadState::Done { id, .. } = task.state
+            {
+                self.is_new_mod = false;
+                self.mod_id = id.to_string();
+            }
+            if !task.state.is_finished() {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+            }
         }
+    }
+
+    /// 是否有正在进行的上传
+    pub fn is_uploading(&self) -> bool {
+        self.task.as_ref().is_some_and(|x| !x.state.is_finished())
+    }
 
-        close_window
+    /// 取消正在进行的上传，结果仍会写入上传记录
+    pub fn cancel_upload(&self) {
+        if let Some(task) = &self.task {
+            task.cancel();
+        }
     }
 
-    fn initialize_steam(&mut self) {
-        // 初始化Steam SDK
-        // 注意：这需要在实际项目中集成steamworks crate并正确初始化
-
-        match steamworks::Client::init_app(APP_ID) {
-            Ok(client) => {
-                // 初始化成功
-                self.steam_initialized = true;
-                self.upload_progress.write().unwrap().status_text =
-                    "Steam初始化成功，准备上传".to_string();
-                self.steam_client = Some(client.clone());
-                // 可以启动一个线程来运行Steam回调
-                std::thread::spawn(move || {
-                    loop {
-                        client.run_callbacks();
-                        std::thread::sleep(std::time::Duration::from_millis(16));
+    /// 标签、可见性、更新说明和其他语言的标题描述
+    fn publish_options_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("可见性:");
+            ComboBox::from_id_salt("upload_visibility")
+                .selected_text(self.visibility.name())
+                .show_ui(ui, |ui| {
+                    for visibility in Visibility::ALL {
+                        ui.selectable_value(&mut self.visibility, visibility, visibility.name());
                     }
                 });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("标签:");
+            for tag in TABLE_TAGS {
+                let mut checked = self.tags.iter().any(|x| x == tag);
+                if ui.checkbox(&mut checked, tag).changed() {
+                    if checked {
+                        self.tags.push(tag.to_string());
+                    } else {
+                        self.tags.retain(|x| x != tag);
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("其他标签:");
+            ui.add(TextEdit::singleline(&mut self.extra_tags).hint_text("用逗号分隔"));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("更新说明:");
+            ui.add_sized(
+                [ui.available_width(), 50.0],
+                TextEdit::multiline(&mut self.change_note),
+            );
+        });
+
+        ui.label("其他语言:");
+        let mut remove = None;
+        for (i, translation) in self.translations.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ComboBox::from_id_salt(("upload_language", i))
+                    .selected_text(language_name(&translation.language))
+                    .show_ui(ui, |ui| {
+                        for (code, name) in LANGUAGES {
+                            ui.selectable_value(&mut translation.language, code.to_string(), name);
+                        }
+                    });
+                ui.add(TextEdit::singleline(&mut translation.title).hint_text("标题"));
+                if ui.button("❌").clicked() {
+                    remove = Some(i);
+                }
+            });
+            ui.add_sized(
+                [ui.available_width(), 40.0],
+                TextEdit::multiline(&mut translation.description).hint_text("描述"),
+            );
+        }
+        if let Some(i) = remove {
+            self.translations.remove(i);
+        }
+        let unused = LANGUAGES
+            .iter()
+            .find(|x| !self.translations.iter().any(|t| t.language == x.0));
+        if let Some((code, _)) = unused
+            && ui.button("添加语言").clicked()
+        {
+            self.translations.push(ItemTranslation {
+                language: code.to_string(),
+                ..Default::default()
+            });
+        }
+    }
+
+    /// 连接创意工坊，已经连接时不会重新初始化
+    pub fn connect(&mut self, session: &mut WorkshopSession) {
+        match session.connect() {
+            Ok(backend) => {
+                self.status_text = format!("{}已连接，准备上传", backend.name());
+                self.backend = Some(backend);
             }
             Err(e) => {
-                // 初始化失败
-                self.steam_error = Some("Steam初始化失败".to_string());
-                self.upload_progress.write().unwrap().status_text =
-                    format!("Steam初始化失败: {}", e);
+                self.backend = None;
+                self.status_text = e;
             }
         }
     }
@@ -256,9 +417,14 @@ impl UploadScreen {
     }
 
     fn load_thumbnail_texture(&mut self, ui: &mut Ui) {
-        // 尝试加载图片文件并创建纹理
-        if let Ok(image_data) = std::fs::read(&self.thumbnail_path) {
-            if let Ok(image) = ::image::load_from_memory(&image_data) {
+        self.thumbnail_texture = None;
+        if self.thumbnail_path.trim().is_empty() {
+            self.thumbnail_info = None;
+            return;
+        }
+        // 尝试加载图片文件并创建纹理，失败时显示原因
+        match thumbnail::load(Path::new(&self.thumbnail_path)) {
+            Ok((info, image)) => {
                 let rgba_image = image.to_rgba8();
                 let pixels = rgba_image.as_raw();
                 let image_buffer = ColorImage::from_rgba_unmultiplied(
@@ -271,6 +437,74 @@ impl UploadScreen {
                     image_buffer,
                     TextureOptions::default(),
                 ));
+                self.thumbnail_info = Some(Ok(info));
+            }
+            Err(e) => self.thumbnail_info = Some(Err(e)),
+        }
+    }
+
+    /// 缩略图的格式、尺寸和问题，以及生成预览图的选项
+    fn thumbnail_info_ui(&mut self, ui: &mut Ui) {
+        let Some(info) = &self.thumbnail_info else {
+            return;
+        };
+        let info = match info {
+            Ok(info) => info,
+            Err(e) => {
+                ui.colored_label(Color32::RED, e);
+                return;
+            }
+        };
+        ui.label(info.summary());
+        for e in info.errors() {
+            ui.colored_label(Color32::RED, e);
+        }
+        for warning in info.warnings() {
+            ui.colored_label(Color32::from_rgb(230, 160, 60), warning);
+        }
+
+        let mut generate = false;
+        ui.horizontal(|ui| {
+            ui.label("裁剪为:");
+            ComboBox::from_id_salt("thumbnail_aspect")
+                .selected_text(self.crop_aspect.name())
+                .show_ui(ui, |ui| {
+                    for aspect in Aspect::ALL {
+                        ui.selectable_value(&mut self.crop_aspect, aspect, aspect.name());
+                    }
+                });
+            ComboBox::from_id_salt("thumbnail_width")
+                .selected_text(format!("宽 {}", self.crop_width))
+                .show_ui(ui, |ui| {
+                    for width in PREVIEW_WIDTHS {
+                        ui.selectable_value(&mut self.crop_width, width, width.to_string());
+                    }
+                });
+            generate = ui.button("生成预览图").clicked();
+        });
+        if generate {
+            self.generate_preview(ui);
+        }
+    }
+
+    /// 裁剪缩放后写在模组文件夹旁边，并改用生成的预览图
+    fn generate_preview(&mut self, ui: &mut Ui) {
+        if self.folder_path.trim().is_empty() || !Path::new(&self.folder_path).is_dir() {
+            self.status_text = "请先选择MOD文件夹".to_string();
+            return;
+        }
+        let out = preview_path(Path::new(&self.folder_path));
+        let result = thumbnail::load(Path::new(&self.thumbnail_path)).and_then(|(_, image)| {
+            thumbnail::make_preview(&image, self.crop_aspect, self.crop_width, &out)
+        });
+        match result {
+            Ok(info) => {
+                self.thumbnail_path = out.display().to_string();
+                self.load_thumbnail_texture(ui);
+                self.status_text = format!("已生成预览图 {}", info.summary());
+            }
+            Err(e) => {
+                self.status_text = format!("生成预览图失败: {}", e);
             }
         }
     }
@@ -282,63 +516,55 @@ impl UploadScreen {
             .pick_folder()
         {
             self.folder_path = path.display().to_string();
+            self.load_mod_data();
         }
     }
 
     fn validate_and_upload(&mut self) {
         // 验证是否有正在编辑的MOD
         if self.mod_name.trim().is_empty() || self.mod_description.trim().is_empty() {
-            self.upload_progress.write().unwrap().status_text =
-                "没有正在编辑的MOD可以上传".to_string();
+            self.status_text = "没有正在编辑的MOD可以上传".to_string();
             return;
         }
 
         // 验证输入
         if self.thumbnail_path.trim().is_empty() || !Path::new(&self.thumbnail_path).exists() {
-            self.upload_progress.write().unwrap().status_text = "请选择有效的缩略图".to_string();
-            return;
-        }
-
-        if self.folder_path.trim().is_empty() || !Path::new(&self.folder_path).exists() {
-            self.upload_progress.write().unwrap().status_text = "请选择有效的MOD文件夹".to_string();
+            self.status_text = "请选择有效的缩略图".to_string();
             return;
         }
-        
-        // 检查Config_Mod和Image_Mod目录
-        let config_mod_path = Path::new(&self.folder_path).join("Config_Mod");
-        let image_mod_path = Path::new(&self.folder_path).join("Image_Mod");
-        
-        // 检查Config_Mod目录是否存在且不为空
-        if !config_mod_path.exists() {
-            self.upload_progress.write().unwrap().status_text = "没有配置文件".to_string();
-            return;
-        }
-        
-        if let Ok(config_entries) = std::fs::read_dir(&config_mod_path) {
-            if config_entries.count() == 0 {
-                self.upload_progress.write().unwrap().status_text = "没有配置文件".to_string();
+        match thumbnail::load(Path::new(&self.thumbnail_path)) {
+            Ok((info, _)) if !info.errors().is_empty() => {
+                self.status_text =
+                    format!("缩略图不符合创意工坊要求: {}", info.errors().join("；"));
                 return;
             }
-        } else {
-            self.upload_progress.write().unwrap().status_text = "无法访问Config_Mod目录".to_string();
+            Ok(_) => {}
+            Err(e) => {
+                self.status_text = format!("缩略图无效: {}", e);
+                return;
+            }
+        }
+
+        if self.folder_path.trim().is_empty() || !Path::new(&self.folder_path).exists() {
+            self.status_text = "请选择有效的MOD文件夹".to_string();
             return;
         }
-        
-        // 检查Image_Mod目录是否存在且不为空
-        if !image_mod_path.exists() {
-            self.upload_progress.write().unwrap().status_text = "没有配置文件".to_string();
+
+        // 按创意工坊的长度限制检查
+        let errors = self.item_update().validate();
+        if !errors.is_empty() {
+            self.status_text = errors.join("\n");
             return;
         }
-        
-        if let Ok(image_entries) = std::fs::read_dir(&image_mod_path) {
-            if image_entries.count() == 0 {
-                self.upload_progress.write().unwrap().status_text = "没有配置文件".to_string();
-                return;
-            }
-        } else {
-            self.upload_progress.write().unwrap().status_text = "无法访问Image_Mod目录".to_string();
+
+        // 检查配置表、图片和moddata.json，有错误时不能上传
+        let report = check_mod_folder(Path::new(&self.folder_path));
+        if report.has_errors() {
+            self.status_text = format!("上传前检查未通过: {}", report.summary());
+            self.preflight = Some(report);
             return;
         }
+        self.preflight = Some(report);
 
         // 显示确认对话框
         self.show_confirm_dialog = true;
@@ -362,6 +588,20 @@ impl UploadScreen {
                     ui.label(format!("缩略图: {}", self.thumbnail_path));
                     ui.add_space(5.0);
                     ui.label(format!("MOD文件夹: {}", self.folder_path));
+                    ui.add_space(5.0);
+                    ui.label(format!("可见性: {}", self.visibility.name()));
+                    ui.add_space(5.0);
+                    ui.label(format!("标签: {}", self.item_update().tags.join(", ")));
+                    if !self.change_note.is_empty() {
+                        ui.add_space(5.0);
+                        ui.label(format!("更新说明: {}", self.change_note));
+                    }
+                    if let Some(report) = &self.preflight
+                        && !report.findings.is_empty()
+                    {
+                        ui.add_space(5.0);
+                        ui.label(format!("上传前检查: {}", report.summary()));
+                    }
                 });
 
                 ui.add_space(20.0);
@@ -388,56 +628,61 @@ impl UploadScreen {
     }
 
     fn upload_to_workshop(&mut self) {
-        let upload_progress = self.upload_progress.clone();
-        let mod_name = self.mod_name.clone();
-        let mod_description = self.mod_description.clone();
-        let mod_id = self.mod_id.clone();
-        let thumbnail_path = self.thumbnail_path.clone();
-        let folder_path = self.folder_path.clone();
-        // 示例代码（需要根据实际Steam SDK调整）：
-        if let Some(client) = self.steam_client.clone() {
-            client.ugc().create_item(
-                APP_ID.into(),
-                FileType::Community,
-                move |result| match result {
-                    Ok((file_id, _)) => {
-                        // 第二个参数是用户需要接受《Steam 创意工坊法律协议》（true），还是不需要（false）？ 参见 创意工坊法律协议，了解更多信息。
-                        let upload_progress2 = upload_progress.clone();
-                        upload_progress.write().unwrap().status_text =
-                            "创建创意工坊物品成功,开始上传".to_string();
-                        let handle = client.ugc().start_item_update(APP_ID.into(), file_id);
-                        let watch_handle = handle
-                            .title(&mod_name)
-                            .description(&mod_description)
-                            .metadata(&format!("{{mod_id = {}}}", mod_id))
-                            .content_path(Path::new(&folder_path))
-                            .preview_path(Path::new(&thumbnail_path))
-                            .submit(None, move |result| match result {
-                                Ok((file_id, _)) => {
-                                    // 第二个参数是用户需要接受《Steam 创意工坊法律协议》（true），还是不需要（false）？ 参见 创意工坊法律协议，了解更多信息。
-                                    upload_progress2.write().unwrap().status_text =
-                                        "上传创意工坊物品成功".to_string();
-                                    client.friends().activate_game_overlay_to_web_page(&format!(
-                                        "steam://url/CommunityFilePage/{}",
-                                        file_id.0
-                                    ));
-                                }
-                                Err(e) => {
-                                    upload_progress2.write().unwrap().status_text =
-                                        format!("上传失败: {}", e);
-                                }
-                            });
-                        upload_progress.write().unwrap().progress = Some(watch_handle);
-                    }
-                    Err(e) => {
-                        upload_progress.write().unwrap().status_text =
-                            format!("创建创意工坊物品失败: {}", e);
-                    }
-                },
-            );
+        let Some(backend) = self.backend.clone() else {
+            return;
+        };
+
+        // 已有物品直接更新，避免重复创建
+        let item_id = if self.is_new_mod {
+            None
+        } else {
+            let Ok(file_id) = self.mod_id.trim().parse::<ItemId>() else {
+                self.status_text = format!("MOD ID 无效: {}", self.mod_id);
+                return;
+            };
+            Some(file_id)
+        };
+        self.status_text = "上传请求已提交，处理中...".to_string();
+        self.task = Some(UploadTask::start(backend, self.item_update(), item_id));
+    }
+
+    /// 按界面上填写的内容生成要提交的物品信息
+    fn item_update(&self) -> ItemUpdate {
+        let mut tags = self.tags.clone();
+        for tag in self.extra_tags.split([',', '，']).map(str::trim) {
+            if !tag.is_empty() && !tags.iter().any(|x| x == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+        ItemUpdate {
+            title: self.mod_name.clone(),
+            description: self.mod_description.clone(),
+            metadata: String::new(),
+            content_path: PathBuf::from(&self.folder_path),
+            preview_path: PathBuf::from(&self.thumbnail_path),
+            tags,
+            visibility: self.visibility,
+            change_note: self.change_note.trim().to_string(),
+            translations: self.translations.clone(),
+        }
+    }
 
-            self.upload_progress.write().unwrap().status_text =
-                "上传请求已提交，处理中...".to_string();
+    /// 读取MOD文件夹中的moddata.json，有物品id时按更新处理
+    pub fn load_mod_data(&mut self) {
+        let path = Path::new(&self.folder_path).join(MOD_DATA_FILE);
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            return;
+        };
+        match serde_json::from_str::<ModData>(&json) {
+            Ok(mod_data) => {
+                self.mod_name = mod_data.name;
+                self.mod_description = mod_data.desc;
+                self.is_new_mod = mod_data.mod_id.is_none();
+                self.mod_id = mod_data.mod_id.map(|x| x.to_string()).unwrap_or_default();
+            }
+            Err(e) => {
+                self.status_text = format!("{}: {}", path.display(), e);
+            }
         }
     }
 
@@ -446,13 +691,10 @@ impl UploadScreen {
         // 测试Steam连接状态
         // 注意：这需要在实际项目中集成steamworks crate并正确实现
 
-        if self.steam_initialized
-            && let Some(client) = &self.steam_client
-        {
+        if let Some(backend) = &self.backend {
             // 获取用户名等信息
-            let friends = client.friends();
-            let username = friends.name();
-            format!("Steam 已登录，当前用户: {}", username)
+            let username = backend.user_name();
+            format!("{} 已登录，当前用户: {}", backend.name(), username)
         } else {
             "Steam 未运行！".to_string()
         }
@@ -465,14 +707,124 @@ impl UploadScreen {
         self.mod_id.clear();
         self.thumbnail_path.clear();
         self.folder_path.clear();
+        self.tags.clear();
+        self.extra_tags.clear();
+        self.visibility = Visibility::default();
+        self.change_note.clear();
+        self.translations.clear();
         self.show_confirm_dialog = false;
-        let mut upload_progress = self.upload_progress.write().unwrap();
-        upload_progress.uploading = false;
-        upload_progress.progress = None;
-        upload_progress.status_text = "准备上传".to_string();
+        self.preflight = None;
+        self.status_text = "准备上传".to_string();
+        self.task = None;
         self.thumbnail_texture = None;
-        // 重置Steam状态
-        self.steam_initialized = false;
-        self.steam_error = None;
+        self.thumbnail_info = None;
+    }
+}
+
+/// 把物品id写入moddata.json，之后的上传会更新同一个物品
+pub fn save_mod_id(folder: &Path, file_id: ItemId) -> Result<(), String> {
+    let path = folder.join(MOD_DATA_FILE);
+    let json = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut mod_data: ModData =
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if mod_data.mod_id == Some(file_id) {
+        return Ok(());
+    }
+    mod_data.mod_id = Some(file_id);
+    let json = serde_json::to_string_pretty(&mod_data).map_err(|e| e.to_string())?;
+    write_atomic(&path, json.as_bytes()).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{TempDir, write_mod_folder};
+    use crate::workshop::LocalBackend;
+
+    /// 上传到本地目录的界面，模组文件夹中已有moddata.json和配置表
+    fn screen() -> (TempDir, UploadScreen) {
+        let root = TempDir::new("upload");
+        let folder = root.join("mod");
+        // 上传前会再次检查模组内容
+        write_mod_folder(&folder);
+        std::fs::write(root.join("thumb.png"), b"png").unwrap();
+
+        let mut screen = UploadScreen {
+            folder_path: folder.display().to_string(),
+            thumbnail_path: root.join("thumb.png").display().to_string(),
+            backend: Some(Arc::new(LocalBackend::new(root.join("workshop")))),
+            ..Default::default()
+        };
+        screen.load_mod_data();
+        (root, screen)
+    }
+
+    fn upload(screen: &mut UploadScreen) -> UploadState {
+        screen.upload_to_workshop();
+        let task = screen.task.as_mut().unwrap();
+        for _ in 0..200 {
+            task.poll();
+            if task.state.is_finished() {
+                return task.state.clone();
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("上传超时");
+    }
+
+    #[test]
+    fn first_upload_saves_id() {
+        let (_root, mut screen) = screen();
+        assert!(screen.is_new_mod);
+        assert_eq!(screen.mod_name, "猫猫");
+
+        assert_eq!(
+            upload(&mut screen),
+            UploadState::Done {
+                id: 1,
+                warnings: Vec::new()
+            }
+        );
+        screen.load_mod_data();
+        assert!(!screen.is_new_mod);
+        assert_eq!(screen.mod_id, "1");
+    }
+
+    #[test]
+    fn later_uploads_update_the_same_item() {
+        let (root, mut screen) = screen();
+        upload(&mut screen);
+        screen.load_mod_data();
+        assert_eq!(
+            upload(&mut screen),
+            UploadState::Done {
+                id: 1,
+                warnings: Vec::new()
+            }
+        );
+        assert!(!root.join("workshop").join("2").exists());
+    }
+
+    #[test]
+    fn every_upload_is_logged() {
+        let (root, mut screen) = screen();
+        upload(&mut screen);
+        screen.load_mod_data();
+        screen.change_note = "修复".to_string();
+        upload(&mut screen);
+
+        let folder = Path::new(&screen.folder_path);
+        let log = crate::published::read_upload_log(folder).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!((log[1].item_id, log[1].error.as_deref()), (Some(1), None));
+        assert_eq!(
+            (log[1].version.as_str(), log[1].change_note.as_str()),
+            ("1.0.0", "修复")
+        );
+        // 记录不在上传的内容中
+        assert!(!folder.join("upload_log.json").exists());
+        let content = root.join("workshop").join("1").join("content");
+        assert!(content.join(MOD_DATA_FILE).exists());
+        assert!(!content.join("upload_log.json").exists());
     }
 }
@@ -0,0 +1,393 @@
+use crate::bean::{ERaceType, TableRow};
+use crate::data::TableData;
+use crate::page::Page;
+use crate::page_render::l10n::l10n_references;
+use crate::sound::SoundRegistry;
+use crate::{Color32, ComboBox, MyApp, RichText, ScrollArea, egui};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "错误",
+            Severity::Warning => "警告",
+        }
+    }
+
+    pub fn color(&self) -> Color32 {
+        match self {
+            Severity::Error => Color32::from_rgb(230, 80, 80),
+            Severity::Warning => Color32::from_rgb(230, 160, 60),
+        }
+    }
+}
+
+/// 一条校验结果，`index` 为校验时所在表中的下标
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub page: Page,
+    pub index: usize,
+    pub row_id: i32,
+    pub message: String,
+}
+
+/// 检查所有表的引用、主键和必填字段
+pub fn validate(table_data: &TableData) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    check_duplicate_ids(&mut issues, &Page::Relic, &table_data.relics);
+    check_duplicate_ids(&mut issues, &Page::Element, &table_data.elements);
+    check_duplicate_ids(&mut issues, &Page::Enemy, &table_data.enemies);
+    check_duplicate_ids(&mut issues, &Page::L10n, &table_data.l10n);
+    check_duplicate_ids(
+        &mut issues,
+        &Page::RaceAttribute,
+        &table_data.race_attributes,
+    );
+
+    // 多语言引用
+    let l10n_ids: HashSet<i32> = table_data.l10n.iter().map(|x| x.id).collect();
+    for r in l10n_references(table_data) {
+        if !l10n_ids.contains(&r.l10n_id) {
+            issues.push(Issue {
+                severity: Severity::Error,
+                page: r.page,
+                index: r.index,
+                row_id: r.row_id,
+                message: format!("{} 引用的多语言id {} 不存在", r.field, r.l10n_id),
+            });
+        }
+    }
+
+    // 种族和属性引用
+    let race_ids: HashSet<i32> = table_data.race_attributes.iter().map(|x| x.id).collect();
+    for (i, x) in table_data.elements.iter().enumerate() {
+        for (field, race) in [("RaceType", x.race_type), ("OtherRace", x.other_race)] {
+            if race != ERaceType::None && !race_ids.contains(&(race as i32)) {
+                issues.push(Issue {
+                    severity: Severity::Error,
+                    page: Page::Element,
+                    index: i,
+                    row_id: x.id,
+                    message: format!("{} {:?} 没有对应的种族属性", field, race),
+                });
+            }
+        }
+        for attribute in &x.attribute {
+            if !race_ids.contains(&attribute.id) {
+                issues.push(Issue {
+                    severity: Severity::Error,
+                    page: Page::Element,
+                    index: i,
+                    row_id: x.id,
+                    message: format!("Attribute 引用的种族属性id {} 不存在", attribute.id),
+                });
+            }
+        }
+    }
+
+    // 必填字段
+    let mut require = |page: &Page, index, row_id, field, value: &str| {
+        if value.trim().is_empty() {
+            issues.push(Issue {
+                severity: Severity::Error,
+                page: page.clone(),
+                index,
+                row_id,
+                message: format!("{} 不能为空", field),
+            });
+        }
+    };
+    for (i, x) in table_data.relics.iter().enumerate() {
+        require(&Page::Relic, i, x.id, "Icon", &x.icon);
+    }
+    for (i, x) in table_data.elements.iter().enumerate() {
+        require(&Page::Element, i, x.id, "Icon", &x.icon);
+    }
+    for (i, x) in table_data.enemies.iter().enumerate() {
+        require(&Page::Enemy, i, x.id, "Icon", &x.icon);
+    }
+    for (i, x) in table_data.race_attributes.iter().enumerate() {
+        require(&Page::RaceAttribute, i, x.id, "Icon", &x.icon);
+    }
+    for (i, x) in table_data.l10n.iter().enumerate() {
+        if x.cn.trim().is_empty() {
+            issues.push(Issue {
+                severity: Severity::Warning,
+                page: Page::L10n,
+                index: i,
+                row_id: x.id,
+                message: "CN 为空".to_string(),
+            });
+        }
+    }
+
+    issues.sort_by_key(|x| x.severity);
+    issues
+}
+
+/// 检查音效字段引用的id是否在 Sound_Mod 注册表中，0 表示不使用音效
+pub fn validate_sounds(table_data: &TableData, sounds: &SoundRegistry) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut check = |page: &Page, index, row_id, field, sound: i32| {
+        if sound != 0 && sounds.find(sound).is_none() {
+            issues.push(Issue {
+                severity: Severity::Warning,
+                page: page.clone(),
+                index,
+                row_id,
+                message: format!("{} 引用的音效id {} 没有注册", field, sound),
+            });
+        }
+    };
+    for (i, x) in table_data.elements.iter().enumerate() {
+        check(&Page::Element, i, x.id, "AttackSound", x.attack_sound);
+        check(&Page::Element, i, x.id, "SelectSound", x.select_sound);
+    }
+    for (i, x) in table_data.enemies.iter().enumerate() {
+        check(&Page::Enemy, i, x.id, "AttackSound", x.attack_sound);
+        check(&Page::Enemy, i, x.id, "ShowSound", x.show_sound);
+    }
+    issues
+}
+
+/// 重复的id，第一次出现的行不报
+fn check_duplicate_ids<T: TableRow>(issues: &mut Vec<Issue>, page: &Page, rows: &[T]) {
+    let mut first: HashMap<i32, usize> = HashMap::new();
+    for (i, row) in rows.iter().enumerate() {
+        let id = row.id();
+        if let Some(&j) = first.get(&id) {
+            issues.push(Issue {
+                severity: Severity::Error,
+                page: page.clone(),
+                index: i,
+                row_id: id,
+                message: format!("id {} 与第 {} 行重复", id, j + 1),
+            });
+        } else {
+            first.insert(id, i);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ValidationWindowState {
+    pub open: bool,
+    issues: Vec<Issue>,
+    filter: Option<Severity>,
+}
+
+/// 重新校验并打开结果窗口
+pub fn open_validation_window(app: &mut MyApp) {
+    let state = &mut app.app_state.validation_window;
+    state.issues = validate(&app.app_state.table_data);
+    // 没有工作区时无法判断音效是否注册
+    if app.app_state.sounds.dir().is_some() {
+        state.issues.extend(validate_sounds(
+            &app.app_state.table_data,
+            &app.app_state.sounds,
+        ));
+        state.issues.sort_by_key(|x| x.severity);
+    }
+    state.open = true;
+}
+
+pub fn render_validation_window(app: &mut MyApp, ctx: &egui::Context) {
+    let mut open = app.app_state.validation_window.open;
+    if !open {
+        return;
+    }
+    let mut jump = None;
+    let mut rerun = false;
+    egui::Window::new("数据校验")
+        .open(&mut open)
+        .default_size([520.0, 400.0])
+        .show(ctx, |ui| {
+            let state = &mut app.app_state.validation_window;
+            let count = |severity| {
+                state
+                    .issues
+                    .iter()
+                    .filter(|x| x.severity == severity)
+                    .count()
+            };
+            let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+            ui.horizontal(|ui| {
+                ui.colored_label(Severity::Error.color(), format!("错误 {}", errors));
+                ui.colored_label(Severity::Warning.color(), format!("警告 {}", warnings));
+                ui.separator();
+                ComboBox::from_id_salt("validation_filter")
+                    .selected_text(state.filter.map_or("全部", |x| x.name()))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut state.filter, None, "全部");
+                        for severity in [Severity::Error, Severity::Warning] {
+                            ui.selectable_value(&mut state.filter, Some(severity), severity.name());
+                        }
+                    });
+                rerun = ui.button("重新校验").clicked();
+            });
+            ui.separator();
+            if state.issues.is_empty() {
+                ui.label("没有发现问题");
+                return;
+            }
+            ScrollArea::vertical().show(ui, |ui| {
+                for issue in state
+                    .issues
+                    .iter()
+                    .filter(|x| state.filter.is_none_or(|f| f == x.severity))
+                {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new(issue.severity.name()).color(issue.severity.color()),
+                        );
+                        let text = format!(
+                            "{} #{}: {}",
+                            issue.page.get_info().title,
+                            issue.row_id,
+                            issue.message
+                        );
+                        if ui.link(text).clicked() {
+                            jump = Some(issue.clone());
+                        }
+                    });
+                }
+            });
+        });
+    app.app_state.validation_window.open = open;
+
+    if rerun {
+        open_validation_window(app);
+    }
+    if let Some(issue) = jump {
+        // 校验之后表格可能已经修改，按 id 重新定位
+        match find_issue_row(&app.app_state.table_data, &issue) {
+            Some(index) => {
+                app.app_data.selected_page = Some(issue.page);
+                app.app_state.scroll_to_row = Some(index);
+            }
+            None => app.app_state.messages.push_back(format!(
+                "{} #{} 已不存在，请重新校验",
+                issue.page.get_info().title,
+                issue.row_id
+            )),
+        }
+    }
+}
+
+/// 校验结果所在行当前的下标，下标处不再是同一 id 时按 id 查找
+fn find_issue_row(table_data: &TableData, issue: &Issue) -> Option<usize> {
+    fn find<T: TableRow>(rows: &[T], index: usize, row_id: i32) -> Option<usize> {
+        if rows.get(index).is_some_and(|x| x.id() == row_id) {
+            return Some(index);
+        }
+        rows.iter().position(|x| x.id() == row_id)
+    }
+    let (index, row_id) = (issue.index, issue.row_id);
+    match issue.page {
+        Page::Relic => find(&table_data.relics, index, row_id),
+        Page::Element => find(&table_data.elements, index, row_id),
+        Page::Enemy => find(&table_data.enemies, index, row_id),
+        Page::L10n => find(&table_data.l10n, index, row_id),
+        Page::RaceAttribute => find(&table_data.race_attributes, index, row_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bean::{Element, ElementAttribute, Localization, RaceAttribute, Relics};
+
+    #[test]
+    fn reports_dangling_references_duplicates_and_empty_icons() {
+        let table_data = TableData {
+            relics: vec![
+                Relics {
+                    id: 1,
+                    icon: "a".to_string(),
+                    name_id: 100,
+                    ..Default::default()
+                },
+                Relics {
+                    id: 1,
+                    icon: " ".to_string(),
+                    name_id: 999,
+                    ..Default::default()
+                },
+            ],
+            elements: vec![Element {
+                id: 7,
+                icon: "e".to_string(),
+                race_type: ERaceType::Cat,
+                other_race: ERaceType::Mod1,
+                attribute: vec![ElementAttribute { id: 2, value: 1 }],
+                ..Default::default()
+            }],
+            l10n: vec![Localization {
+                id: 100,
+                cn: "名字".to_string(),
+                ..Default::default()
+            }],
+            race_attributes: vec![RaceAttribute {
+                id: 2,
+                icon: "r".to_string(),
+                name_id: 100,
+                desc_id: 0,
+            }],
+            ..Default::default()
+        };
+        let issues: Vec<_> = validate(&table_data)
+            .into_iter()
+            .map(|x| (x.page, x.index, x.message))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (Page::Relic, 1, "id 1 与第 1 行重复".to_string()),
+                (
+                    Page::Relic,
+                    1,
+                    "NameId 引用的多语言id 999 不存在".to_string()
+                ),
+                (
+                    Page::Element,
+                    0,
+                    "OtherRace Mod1 没有对应的种族属性".to_string()
+                ),
+                (Page::Relic, 1, "Icon 不能为空".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn jumps_follow_rows_moved_after_validation() {
+        let relic = |id| Relics {
+            id,
+            ..Default::default()
+        };
+        let mut table_data = TableData {
+            relics: vec![relic(1), relic(2), relic(2)],
+            ..Default::default()
+        };
+        let issue = |index, row_id| Issue {
+            severity: Severity::Error,
+            page: Page::Relic,
+            index,
+            row_id,
+            message: String::new(),
+        };
+        // 重复的 id 仍然定位到原来的行
+        assert_eq!(find_issue_row(&table_data, &issue(2, 2)), Some(2));
+
+        table_data.relics.insert(0, relic(3));
+        assert_eq!(find_issue_row(&table_data, &issue(0, 1)), Some(1));
+        table_data.relics.retain(|x| x.id != 1);
+        assert_eq!(find_issue_row(&table_data, &issue(0, 1)), None);
+    }
+}
@@ -0,0 +1,282 @@
+mod local;
+mod session;
+#[cfg(feature = "steam")]
+mod steam;
+
+pub use local::LocalBackend;
+pub use session::{SessionStatus, WorkshopSession};
+#[cfg(feature = "steam")]
+pub use steam::SteamBackend;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// 创意工坊物品id
+pub type ItemId = u64;
+
+/// 设置后使用本地目录代替创意工坊，方便离线测试上传流程
+pub const LOCAL_WORKSHOP_ENV: &str = "RHE_LOCAL_WORKSHOP";
+
+/// 创建物品的回调，在后台线程中调用
+pub type ItemCallback = Box<dyn FnOnce(Result<ItemId, String>) + Send>;
+
+/// 更新物品的结果，主要内容已提交但部分语言失败时记在 `warnings` 中
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemUpdated {
+    pub id: ItemId,
+    pub warnings: Vec<String>,
+}
+
+/// 更新物品的回调，在后台线程中调用
+pub type UpdateCallback = Box<dyn FnOnce(Result<ItemUpdated, String>) + Send>;
+
+/// 查询已发布物品的回调，在后台线程中调用
+pub type ItemsCallback = Box<dyn FnOnce(Result<Vec<PublishedItem>, String>) + Send>;
+
+/// 当前用户发布过的物品
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublishedItem {
+    pub id: ItemId,
+    pub title: String,
+    pub updated: Option<chrono::DateTime<chrono::Local>>,
+}
+
+/// 创意工坊的长度限制，按UTF-8字节计算
+pub const MAX_TITLE_BYTES: usize = 128;
+pub const MAX_DESCRIPTION_BYTES: usize = 8000;
+pub const MAX_CHANGE_NOTE_BYTES: usize = 8000;
+pub const MAX_METADATA_BYTES: usize = 5000;
+pub const MAX_TAG_BYTES: usize = 255;
+
+/// 除默认的标题描述外可以额外填写的语言，Steam API语言代码和显示名称
+pub const LANGUAGES: [(&str, &str); 3] = [
+    ("english", "英语"),
+    ("japanese", "日语"),
+    ("tchinese", "繁体中文"),
+];
+
+/// 物品的可见性
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Visibility {
+    #[default]
+    Public,
+    FriendsOnly,
+    Private,
+    Unlisted,
+}
+
+impl Visibility {
+    pub const ALL: [Visibility; 4] = [
+        Visibility::Public,
+        Visibility::FriendsOnly,
+        Visibility::Private,
+        Visibility::Unlisted,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Visibility::Public => "公开",
+            Visibility::FriendsOnly => "仅好友",
+            Visibility::Private => "私密",
+            Visibility::Unlisted => "不公开列出",
+        }
+    }
+}
+
+/// 某个语言的标题和描述
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemTranslation {
+    pub language: String,
+    pub title: String,
+    pub description: String,
+}
+
+/// 上传物品时提交的内容
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemUpdate {
+    pub title: String,
+    pub description: String,
+    pub metadata: String,
+    pub content_path: PathBuf,
+    pub preview_path: PathBuf,
+    pub tags: Vec<String>,
+    pub visibility: Visibility,
+    /// 更新说明，为空时不提交
+    pub change_note: String,
+    pub translations: Vec<ItemTranslation>,
+}
+
+impl ItemUpdate {
+    /// 按创意工坊的限制检查各个字段，返回所有错误
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut check_len = |name: &str, text: &str, max: usize| {
+            if text.len() > max {
+                errors.push(format!(
+                    "{}过长: {} 字节，最多 {} 字节",
+                    name,
+                    text.len(),
+                    max
+                ));
+            }
+        };
+        check_len("标题", &self.title, MAX_TITLE_BYTES);
+        check_len("描述", &self.description, MAX_DESCRIPTION_BYTES);
+        check_len("更新说明", &self.change_note, MAX_CHANGE_NOTE_BYTES);
+        check_len("元数据", &self.metadata, MAX_METADATA_BYTES);
+        for tag in &self.tags {
+            check_len(&format!("标签 {}", tag), tag, MAX_TAG_BYTES);
+        }
+        for translation in &self.translations {
+            let name = language_name(&translation.language);
+            check_len(
+                &format!("{}标题", name),
+                &translation.title,
+                MAX_TITLE_BYTES,
+            );
+            check_len(
+                &format!("{}描述", name),
+                &translation.description,
+                MAX_DESCRIPTION_BYTES,
+            );
+        }
+
+        if self.title.trim().is_empty() {
+            errors.push("标题不能为空".to_string());
+        }
+        for tag in &self.tags {
+            if tag.trim().is_empty() {
+                errors.push("标签不能为空".to_string());
+            } else if tag.contains(',') || tag.chars().any(char::is_control) {
+                errors.push(format!("标签 {} 不能包含逗号或控制字符", tag));
+            }
+        }
+        for (i, translation) in self.translations.iter().enumerate() {
+            let name = language_name(&translation.language);
+            if !LANGUAGES.iter().any(|x| x.0 == translation.language) {
+                errors.push(format!("不支持的语言: {}", translation.language));
+            } else if self.translations[..i]
+                .iter()
+                .any(|x| x.language == translation.language)
+            {
+                errors.push(format!("{}重复填写", name));
+            }
+            if translation.title.trim().is_empty() {
+                errors.push(format!("{}标题不能为空", name));
+            }
+        }
+        errors
+    }
+}
+
+/// 语言代码对应的显示名称
+pub fn language_name(language: &str) -> &str {
+    LANGUAGES
+        .iter()
+        .find(|x| x.0 == language)
+        .map_or(language, |x| x.1)
+}
+
+/// 上传阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdatePhase {
+    Invalid,
+    PreparingConfig,
+    PreparingContent,
+    UploadingContent,
+    UploadingPreview,
+    Committing,
+}
+
+/// 上传进度，字节数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateProgress {
+    pub phase: UpdatePhase,
+    pub uploaded: u64,
+    pub total: u64,
+}
+
+/// 正在进行的上传
+pub trait UpdateWatch: Send + Sync {
+    fn progress(&self) -> UpdateProgress;
+
+    /// 停止上传，返回是否真的停止了。Steam没有取消提交的接口，默认不支持
+    fn cancel(&self) -> bool {
+        false
+    }
+}
+
+/// 创意工坊的发布接口
+pub trait WorkshopBackend: Send + Sync {
+    /// 显示在界面上的名称
+    fn name(&self) -> &'static str;
+
+    /// 当前登录的用户
+    fn user_name(&self) -> String;
+
+    /// 创建一个空物品
+    fn create_item(&self, done: ItemCallback);
+
+    /// 提交物品的内容和各语言的标题描述，返回用于查询进度的句柄
+    fn update_item(
+        &self,
+        id: ItemId,
+        update: &ItemUpdate,
+        done: UpdateCallback,
+    ) -> Box<dyn UpdateWatch>;
+
+    /// 上传成功后打开物品页面
+    fn show_item(&self, id: ItemId);
+
+    /// 查询当前用户发布的所有物品，最近更新的在前
+    fn published_items(&self, done: ItemsCallback);
+
+    /// 程序退出时停止后台线程，之后不能再使用
+    fn shutdown(&self) {}
+}
+
+/// 按编译选项和环境变量选择后端
+pub fn connect() -> Result<Arc<dyn WorkshopBackend>, String> {
+    if let Ok(dir) = std::env::var(LOCAL_WORKSHOP_ENV) {
+        return Ok(Arc::new(LocalBackend::new(dir)));
+    }
+    #[cfg(feature = "steam")]
+    {
+        SteamBackend::init().map(|x| Arc::new(x) as Arc<dyn WorkshopBackend>)
+    }
+    #[cfg(not(feature = "steam"))]
+    {
+        Ok(Arc::new(LocalBackend::new(
+            std::env::temp_dir().join("RouletteHeroWorkshop"),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_workshop_limits() {
+        let mut update = ItemUpdate {
+            title: "猫".repeat(42),
+            tags: vec!["Units".to_string()],
+            translations: vec![ItemTranslation {
+                language: "english".to_string(),
+                title: "Cats".to_string(),
+                description: String::new(),
+            }],
+            ..Default::default()
+        };
+        assert!(update.validate().is_empty());
+
+        // 按字节计算，43个汉字超过128字节
+        update.title.push('猫');
+        update.tags.push("a,b".to_string());
+        update.change_note = "x".repeat(MAX_CHANGE_NOTE_BYTES + 1);
+        update.translations.push(update.translations[0].clone());
+        let errors = update.validate();
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(errors[0].starts_with("标题过长"));
+    }
+}
@@ -0,0 +1,443 @@
+use super::{
+    ItemCallback, ItemId, ItemUpdate, ItemUpdated, ItemsCallback, PublishedItem, UpdateCallback,
+    UpdatePhase, UpdateProgress, UpdateWatch, WorkshopBackend,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+const CONTENT_DIR: &str = "content";
+/// 复制中的内容，提交时才替换 content
+const STAGING_DIR: &str = "content.staging";
+const ITEM_FILE: &str = "item.json";
+
+/// 把上传记录到本地目录的后端，每个物品一个以id命名的子目录
+pub struct LocalBackend {
+    root: PathBuf,
+}
+
+impl LocalBackend {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn item_dir(&self, id: ItemId) -> PathBuf {
+        self.root.join(id.to_string())
+    }
+
+    fn next_id(&self) -> ItemId {
+        fs::read_dir(&self.root)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|x| x.file_name().to_str()?.parse::<ItemId>().ok())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .unwrap_or(0)
+            + 1
+    }
+
+    fn create(&self) -> io::Result<ItemId> {
+        fs::create_dir_all(&self.root)?;
+        let id = self.next_id();
+        fs::create_dir(self.item_dir(id))?;
+        Ok(id)
+    }
+
+    /// 已经上传过内容的物品，只创建没上传的物品没有item.json
+    fn items(&self) -> io::Result<Vec<PublishedItem>> {
+        let mut items = Vec::new();
+        if !self.root.exists() {
+            return Ok(items);
+        }
+        for entry in fs::read_dir(&self.root)?.flatten() {
+            let Some(id) = entry
+                .file_name()
+                .to_str()
+                .and_then(|x| x.parse::<ItemId>().ok())
+            else {
+                continue;
+            };
+            let Ok(json) = fs::read_to_string(entry.path().join(ITEM_FILE)) else {
+                continue;
+            };
+            let item: serde_json::Value = serde_json::from_str(&json).map_err(io::Error::other)?;
+            items.push(PublishedItem {
+                id,
+                title: item["title"].as_str().unwrap_or_default().to_string(),
+                updated: item["time"]
+                    .as_str()
+                    .and_then(|x| chrono::DateTime::parse_from_rfc3339(x).ok())
+                    .map(|x| x.with_timezone(&chrono::Local)),
+            });
+        }
+        items.sort_by(|a, b| b.updated.cmp(&a.updated).then(b.id.cmp(&a.id)));
+        Ok(items)
+    }
+}
+
+impl WorkshopBackend for LocalBackend {
+    fn name(&self) -> &'static str {
+        "本地目录"
+    }
+
+    fn user_name(&self) -> String {
+        std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "local".to_string())
+    }
+
+    fn create_item(&self, done: ItemCallback) {
+        let result = self
+            .create()
+            .map_err(|e| format!("{}: {}", self.root.display(), e));
+        std::thread::spawn(move || done(result));
+    }
+
+    fn update_item(
+        &self,
+        id: ItemId,
+        update: &ItemUpdate,
+        done: UpdateCallback,
+    ) -> Box<dyn UpdateWatch> {
+        let watch = LocalWatch {
+            progress: Arc::new(Mutex::new(UpdateProgress {
+                phase: UpdatePhase::PreparingConfig,
+                uploaded: 0,
+                total: 0,
+            })),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+        let progress = watch.progress.clone();
+        let cancelled = watch.cancelled.clone();
+        let dir = self.item_dir(id);
+        let update = update.clone();
+        let user = self.user_name();
+        std::thread::spawn(move || {
+            let result = write_item(&dir, &update, &user, &progress, &cancelled)
+                .map(|_| ItemUpdated {
+                    id,
+                    warnings: Vec::new(),
+                })
+                .map_err(|e| format!("{}: {}", dir.display(), e));
+            // 和Steam一样，结束后的句柄不再有效
+            progress.lock().unwrap().phase = UpdatePhase::Invalid;
+            done(result);
+        });
+        Box::new(watch)
+    }
+
+    fn show_item(&self, id: ItemId) {
+        log::info!("本地物品: {}", self.item_dir(id).display());
+    }
+
+    fn published_items(&self, done: ItemsCallback) {
+        let result = self
+            .items()
+            .map_err(|e| format!("{}: {}", self.root.display(), e));
+        std::thread::spawn(move || done(result));
+    }
+}
+
+/// 复制内容和预览图，并写入物品信息
+fn write_item(
+    dir: &Path,
+    update: &ItemUpdate,
+    user: &str,
+    progress: &Mutex<UpdateProgress>,
+    cancelled: &AtomicBool,
+) -> io::Result<()> {
+    let set_phase = |phase| progress.lock().unwrap().phase = phase;
+    if !dir.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "物品不存在"));
+    }
+
+    set_phase(UpdatePhase::PreparingContent);
+    let files = list_files(&update.content_path)?;
+    progress.lock().unwrap().total = files.iter().map(|x| x.1).sum();
+    let staging = dir.join(STAGING_DIR);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+
+    set_phase(UpdatePhase::UploadingContent);
+    // 取消或失败时丢弃复制到一半的内容，保留上一次的 content
+    let copied = copy_files(&update.content_path, &staging, &files, progress, cancelled);
+    if let Err(e) = copied {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    set_phase(UpdatePhase::UploadingPreview);
+    if let Some(extension) = update.preview_path.extension() {
+        fs::copy(
+            &update.preview_path,
+            dir.join("preview").with_extension(extension),
+        )?;
+    }
+
+    set_phase(UpdatePhase::Committing);
+    let content = dir.join(CONTENT_DIR);
+    if content.exists() {
+        fs::remove_dir_all(&content)?;
+    }
+    fs::rename(&staging, &content)?;
+    let time = chrono::Local::now().to_rfc3339();
+    // 和创意工坊一样保留以前的更新说明
+    let mut changes = fs::read_to_string(dir.join(ITEM_FILE))
+        .ok()
+        .and_then(|x| serde_json::from_str::<serde_json::Value>(&x).ok())
+        .and_then(|x| x.get("changes")?.as_array().cloned())
+        .unwrap_or_default();
+    if !update.change_note.is_empty() {
+        changes.push(serde_json::json!({ "time": time, "note": update.change_note }));
+    }
+    let translations: serde_json::Map<String, serde_json::Value> = update
+        .translations
+        .iter()
+        .map(|x| {
+            let text = serde_json::json!({ "title": x.title, "description": x.description });
+            (x.language.clone(), text)
+        })
+        .collect();
+    let item = serde_json::json!({
+        "title": update.title,
+        "description": update.description,
+        "metadata": update.metadata,
+        "tags": update.tags,
+        "visibility": update.visibility.name(),
+        "translations": translations,
+        "changes": changes,
+        "user": user,
+        "time": time,
+    });
+    let json = serde_json::to_string_pretty(&item).map_err(io::Error::other)?;
+    crate::data::write_atomic(&dir.join(ITEM_FILE), json.as_bytes())
+}
+
+/// 把文件复制到目标目录，每个文件之前检查是否取消
+fn copy_files(
+    from: &Path,
+    to: &Path,
+    files: &[(PathBuf, u64)],
+    progress: &Mutex<UpdateProgress>,
+    cancelled: &AtomicBool,
+) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for (relative, size) in files {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "已取消"));
+        }
+        let target = to.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from.join(relative), target)?;
+        progress.lock().unwrap().uploaded += size;
+    }
+    Ok(())
+}
+
+/// 目录下所有文件的相对路径和大小
+fn list_files(root: &Path) -> io::Result<Vec<(PathBuf, u64)>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative) = dirs.pop() {
+        for entry in fs::read_dir(root.join(&relative))? {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push((path, metadata.len()));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+struct LocalWatch {
+    progress: Arc<Mutex<UpdateProgress>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl UpdateWatch for LocalWatch {
+    fn progress(&self) -> UpdateProgress {
+        *self.progress.lock().unwrap()
+    }
+
+    fn cancel(&self) -> bool {
+        self.cancelled.store(true, Ordering::Relaxed);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use crate::workshop::ItemTranslation;
+    use std::sync::mpsc;
+
+    /// 有一个模组文件夹和预览图的目录
+    fn fixture() -> (TempDir, LocalBackend, ItemUpdate) {
+        let root = TempDir::new("workshop");
+        let content = root.join("mod");
+        fs::create_dir_all(content.join("Config_Mod")).unwrap();
+        fs::write(content.join("Config_Mod").join("a.bytes"), [0u8; 10]).unwrap();
+        fs::write(content.join("moddata.json"), "{}").unwrap();
+        fs::write(root.join("thumb.png"), b"png").unwrap();
+
+        let backend = LocalBackend::new(root.join("workshop"));
+        let update = ItemUpdate {
+            title: "猫猫".to_string(),
+            content_path: content,
+            preview_path: root.join("thumb.png"),
+            tags: vec!["Units".to_string()],
+            change_note: "第一版".to_string(),
+            translations: vec![ItemTranslation {
+                language: "english".to_string(),
+                title: "Cats".to_string(),
+                description: String::new(),
+            }],
+            ..Default::default()
+        };
+        (root, backend, update)
+    }
+
+    fn create(backend: &LocalBackend) -> ItemId {
+        let (tx, rx) = mpsc::channel();
+        backend.create_item(Box::new(move |x| tx.send(x).unwrap()));
+        rx.recv().unwrap().unwrap()
+    }
+
+    fn update(
+        backend: &LocalBackend,
+        id: ItemId,
+        update: &ItemUpdate,
+    ) -> (Result<ItemId, String>, Box<dyn UpdateWatch>) {
+        let (tx, rx) = mpsc::channel();
+        let watch = backend.update_item(id, update, Box::new(move |x| tx.send(x).unwrap()));
+        (rx.recv().unwrap().map(|x| x.id), watch)
+    }
+
+    fn read_item(backend: &LocalBackend, id: ItemId) -> serde_json::Value {
+        let json = fs::read_to_string(backend.item_dir(id).join(ITEM_FILE)).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn created_items_get_increasing_ids() {
+        let (_root, backend, _) = fixture();
+        assert_eq!(create(&backend), 1);
+        assert_eq!(create(&backend), 2);
+    }
+
+    #[test]
+    fn update_copies_content_and_records_item() {
+        let (_root, backend, item) = fixture();
+        let id = create(&backend);
+        let (result, watch) = update(&backend, id, &item);
+        assert_eq!(result, Ok(1));
+        let progress = watch.progress();
+        assert_eq!(progress.phase, UpdatePhase::Invalid);
+        assert_eq!((progress.uploaded, progress.total), (12, 12));
+
+        let dir = backend.item_dir(id);
+        assert!(
+            dir.join(CONTENT_DIR)
+                .join("Config_Mod")
+                .join("a.bytes")
+                .exists()
+        );
+        assert!(dir.join("preview.png").exists());
+        let item = read_item(&backend, id);
+        assert_eq!(item["title"], "猫猫");
+        assert_eq!(item["tags"][0], "Units");
+        assert_eq!(item["translations"]["english"]["title"], "Cats");
+    }
+
+    #[test]
+    fn later_updates_keep_change_notes() {
+        let (_root, backend, item) = fixture();
+        let id = create(&backend);
+        update(&backend, id, &item).0.unwrap();
+        let second = ItemUpdate {
+            change_note: "修复".to_string(),
+            ..item
+        };
+        update(&backend, id, &second).0.unwrap();
+        let item = read_item(&backend, id);
+        assert_eq!(item["changes"].as_array().unwrap().len(), 2);
+        assert_eq!(item["changes"][1]["note"], "修复");
+    }
+
+    #[test]
+    fn published_items_skip_items_without_content() {
+        let (_root, backend, item) = fixture();
+        let id = create(&backend);
+        create(&backend);
+        update(&backend, id, &item).0.unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        backend.published_items(Box::new(move |x| tx.send(x).unwrap()));
+        let items = rx.recv().unwrap().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!((items[0].id, items[0].title.as_str()), (1, "猫猫"));
+    }
+
+    #[test]
+    fn cancelled_update_keeps_previous_content() {
+        let (_root, backend, item) = fixture();
+        let id = create(&backend);
+        update(&backend, id, &item).0.unwrap();
+        fs::write(item.content_path.join("moddata.json"), "{\"new\": 1}").unwrap();
+
+        let dir = backend.item_dir(id);
+        let progress = Mutex::new(UpdateProgress {
+            phase: UpdatePhase::PreparingConfig,
+            uploaded: 0,
+            total: 0,
+        });
+        let cancelled = AtomicBool::new(true);
+        let result = write_item(&dir, &item, "", &progress, &cancelled);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Interrupted);
+
+        let content = dir.join(CONTENT_DIR);
+        assert_eq!(
+            fs::read_to_string(content.join("moddata.json")).unwrap(),
+            "{}"
+        );
+        assert!(content.join("Config_Mod").join("a.bytes").exists());
+        assert!(!dir.join(STAGING_DIR).exists());
+        assert_eq!(
+            read_item(&backend, id)["changes"].as_array().unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn update_replaces_removed_files() {
+        let (_root, backend, item) = fixture();
+        let id = create(&backend);
+        update(&backend, id, &item).0.unwrap();
+        fs::remove_file(item.content_path.join("Config_Mod").join("a.bytes")).unwrap();
+        update(&backend, id, &item).0.unwrap();
+
+        let content = backend.item_dir(id).join(CONTENT_DIR);
+        assert!(!content.join("Config_Mod").join("a.bytes").exists());
+        assert!(content.join("moddata.json").exists());
+        assert!(!backend.item_dir(id).join(STAGING_DIR).exists());
+    }
+
+    #[test]
+    fn missing_items_cannot_be_updated() {
+        let (_root, backend, item) = fixture();
+        assert!(update(&backend, 9, &item).0.is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use super::{WorkshopBackend, connect};
+use std::sync::Arc;
+
+/// 创意工坊的连接状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionStatus {
+    NotConnected,
+    Connected { backend: &'static str, user: String },
+    Failed(String),
+}
+
+impl SessionStatus {
+    pub fn describe(&self) -> String {
+        match self {
+            SessionStatus::NotConnected => "未连接".to_string(),
+            SessionStatus::Connected { backend, user } => {
+                format!("已连接 ({})，当前用户: {}", backend, user)
+            }
+            SessionStatus::Failed(e) => e.clone(),
+        }
+    }
+}
+
+/// 整个程序共用的创意工坊连接，Steam只初始化一次，退出时关闭
+#[derive(Default)]
+pub struct WorkshopSession {
+    backend: Option<Arc<dyn WorkshopBackend>>,
+    user: String,
+    error: Option<String>,
+}
+
+impl WorkshopSession {
+    /// 还没有连接时连接创意工坊，已连接时返回同一个后端
+    pub fn connect(&mut self) -> Result<Arc<dyn WorkshopBackend>, String> {
+        if let Some(backend) = &self.backend {
+            return Ok(backend.clone());
+        }
+        match connect() {
+            Ok(backend) => {
+                self.user = backend.user_name();
+                self.error = None;
+                self.backend = Some(backend.clone());
+                Ok(backend)
+            }
+            Err(e) => {
+                self.error = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    /// 已连接的后端，不会尝试连接
+    pub fn backend(&self) -> Option<Arc<dyn WorkshopBackend>> {
+        self.backend.clone()
+    }
+
+    pub fn status(&self) -> SessionStatus {
+        match (&self.backend, &self.error) {
+            (Some(backend), _) => SessionStatus::Connected {
+                backend: backend.name(),
+                user: self.user.clone(),
+            },
+            (None, Some(e)) => SessionStatus::Failed(e.clone()),
+            (None, None) => SessionStatus::NotConnected,
+        }
+    }
+
+    /// 停止回调线程并断开连接
+    pub fn shutdown(&mut self) {
+        if let Some(backend) = self.backend.take() {
+            backend.shutdown();
+        }
+        self.user.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workshop::LocalBackend;
+
+    #[test]
+    fn reuses_one_backend_until_shutdown() {
+        let mut session = WorkshopSession::default();
+        assert_eq!(session.status(), SessionStatus::NotConnected);
+        assert!(session.backend().is_none());
+
+        let backend: Arc<dyn WorkshopBackend> = Arc::new(LocalBackend::new("workshop"));
+        session.backend = Some(backend.clone());
+        session.user = "猫猫".to_string();
+        assert!(Arc::ptr_eq(&session.connect().unwrap(), &backend));
+        assert_eq!(
+            session.status(),
+            SessionStatus::Connected {
+                backend: "本地目录",
+                user: "猫猫".to_string()
+            }
+        );
+
+        session.shutdown();
+        assert_eq!(session.status(), SessionStatus::NotConnected);
+        assert!(session.backend().is_none());
+    }
+}
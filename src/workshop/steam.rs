@@ -0,0 +1,284 @@
+use super::{
+    ItemCallback, ItemId, ItemTranslation, ItemUpdate, ItemUpdated, ItemsCallback, PublishedItem,
+    UpdateCallback, UpdatePhase, UpdateProgress, UpdateWatch, Visibility, WorkshopBackend,
+};
+use std::ffi::CString;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use steamworks::{
+    AccountId, AppIDs, Client, FileType, PublishedFileId, PublishedFileVisibility, SteamError,
+    UGCType, UpdateStatus, UpdateWatchHandle, UserList, UserListOrder, sys,
+};
+
+const APP_ID: u32 = 3371510;
+
+/// 运行Steam回调的间隔
+const CALLBACK_INTERVAL: Duration = Duration::from_millis(16);
+/// 等待一种语言提交结果的最长时间
+const TRANSLATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 通过Steam客户端发布到创意工坊
+pub struct SteamBackend {
+    client: Client,
+    pump: Mutex<Option<CallbackPump>>,
+    /// 关闭后后台线程不再调用SDK
+    running: Arc<AtomicBool>,
+}
+
+/// 运行Steam回调的后台线程，关闭channel后退出
+struct CallbackPump {
+    stop: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl SteamBackend {
+    /// 初始化Steam SDK，并在后台线程中运行回调
+    pub fn init() -> Result<Self, String> {
+        let client = Client::init_app(APP_ID).map_err(|e| format!("Steam初始化失败: {}", e))?;
+        let callbacks = client.clone();
+        let (stop, receiver) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(CALLBACK_INTERVAL) {
+                callbacks.run_callbacks();
+            }
+        });
+        Ok(Self {
+            client,
+            pump: Mutex::new(Some(CallbackPump { stop, thread })),
+            running: Arc::new(AtomicBool::new(true)),
+        })
+    }
+}
+
+impl Drop for SteamBackend {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl WorkshopBackend for SteamBackend {
+    fn name(&self) -> &'static str {
+        "Steam"
+    }
+
+    fn user_name(&self) -> String {
+        self.client.friends().name()
+    }
+
+    fn create_item(&self, done: ItemCallback) {
+        self.client
+            .ugc()
+            .create_item(APP_ID.into(), FileType::Community, move |result| {
+                // 第二个参数表示用户是否需要接受《Steam 创意工坊法律协议》
+                done(result.map(|(id, _)| id.0).map_err(|e| e.to_string()))
+            });
+    }
+
+    fn update_item(
+        &self,
+        id: ItemId,
+        update: &ItemUpdate,
+        done: UpdateCallback,
+    ) -> Box<dyn UpdateWatch> {
+        let visibility = match update.visibility {
+            Visibility::Public => PublishedFileVisibility::Public,
+            Visibility::FriendsOnly => PublishedFileVisibility::FriendsOnly,
+            Visibility::Private => PublishedFileVisibility::Private,
+            Visibility::Unlisted => PublishedFileVisibility::Unlisted,
+        };
+        let change_note = Some(update.change_note.as_str()).filter(|x| !x.is_empty());
+        let translations = update.translations.clone();
+        let client = self.client.clone();
+        let running = self.running.clone();
+        let handle = self
+            .client
+            .ugc()
+            .start_item_update(APP_ID.into(), PublishedFileId(id))
+            .title(&update.title)
+            .description(&update.description)
+            .metadata(&update.metadata)
+            .content_path(&update.content_path)
+            .preview_path(&update.preview_path)
+            .tags(update.tags.clone(), false)
+            .visibility(visibility)
+            .submit(change_note, move |result| match result {
+                // 主要内容提交后再逐个提交其他语言，不阻塞回调线程。
+                // 此时物品已经更新，其他语言失败只作为警告
+                Ok((id, _)) if !translations.is_empty() => {
+                    std::thread::spawn(move || {
+                        let warnings = translations
+                            .iter()
+                            .filter_map(|x| {
+                                let result = submit_translation(id.0, x, &running);
+                                result.err().map(|e| format!("{}: {}", x.language, e))
+                            })
+                            .collect();
+                        drop(client);
+                        done(Ok(ItemUpdated { id: id.0, warnings }));
+                    });
+                }
+                result => done(
+                    result
+                        .map(|(id, _)| ItemUpdated {
+                            id: id.0,
+                            warnings: Vec::new(),
+                        })
+                        .map_err(|e| e.to_string()),
+                ),
+            });
+        Box::new(SteamWatch(handle))
+    }
+
+    fn show_item(&self, id: ItemId) {
+        self.client
+            .friends()
+            .activate_game_overlay_to_web_page(&format!("steam://url/CommunityFilePage/{}", id));
+    }
+
+    fn published_items(&self, done: ItemsCallback) {
+        let account = self.client.user().steam_id().account_id();
+        query_published(self.client.clone(), account, 1, Vec::new(), done);
+    }
+
+    fn shutdown(&self) {
+        self.running.store(false, Ordering::Relaxed);
+        let Some(pump) = self.pump.lock().unwrap().take() else {
+            return;
+        };
+        drop(pump.stop);
+        if pump.thread.join().is_err() {
+            log::warn!("Steam回调线程异常退出");
+        }
+    }
+}
+
+/// 逐页查询用户发布的物品，每页最多50个
+fn query_published(
+    client: Client,
+    account: AccountId,
+    page: u32,
+    mut items: Vec<PublishedItem>,
+    done: ItemsCallback,
+) {
+    let query = client.ugc().query_user(
+        account,
+        UserList::Published,
+        UGCType::Items,
+        UserListOrder::LastUpdatedDesc,
+        AppIDs::ConsumerAppId(APP_ID.into()),
+        page,
+    );
+    let query = match query {
+        Ok(query) => query,
+        Err(e) => return done(Err(e.to_string())),
+    };
+    let next = client.clone();
+    query.fetch(move |result| {
+        let results = match result {
+            Ok(results) => results,
+            Err(e) => return done(Err(e.to_string())),
+        };
+        items.extend(results.iter().flatten().map(|x| {
+            PublishedItem {
+                id: x.published_file_id.0,
+                title: x.title,
+                updated: chrono::DateTime::from_timestamp(x.time_updated as i64, 0)
+                    .map(|x| x.with_timezone(&chrono::Local)),
+            }
+        }));
+        if results.returned_results() == 0 || items.len() >= results.total_results() as usize {
+            done(Ok(items));
+        } else {
+            // 回调执行时持有Steam的回调锁，不能在这里直接发起下一个查询
+            std::thread::spawn(move || query_published(next, account, page + 1, items, done));
+        }
+    });
+}
+
+/// steamworks 0.12 没有封装 SetItemUpdateLanguage，其他语言的标题描述直接调用SDK提交。
+/// 也无法注册提交结果的回调，只能轮询调用是否完成再读取结果
+fn submit_translation(
+    id: ItemId,
+    translation: &ItemTranslation,
+    running: &AtomicBool,
+) -> Result<(), String> {
+    let to_c = |text: &str| CString::new(text).map_err(|e| e.to_string());
+    let language = to_c(&translation.language)?;
+    let title = to_c(&translation.title)?;
+    let description = to_c(&translation.description)?;
+    let closed = || format!("Steam连接已关闭，没有提交{}", translation.language);
+    if !running.load(Ordering::Relaxed) {
+        return Err(closed());
+    }
+    unsafe {
+        let ugc = sys::SteamAPI_SteamUGC_v021();
+        let handle = sys::SteamAPI_ISteamUGC_StartItemUpdate(ugc, APP_ID, id);
+        let ok = sys::SteamAPI_ISteamUGC_SetItemUpdateLanguage(ugc, handle, language.as_ptr())
+            && sys::SteamAPI_ISteamUGC_SetItemTitle(ugc, handle, title.as_ptr())
+            && sys::SteamAPI_ISteamUGC_SetItemDescription(ugc, handle, description.as_ptr());
+        if !ok {
+            return Err(format!("无法设置{}的标题和描述", translation.language));
+        }
+        let call = sys::SteamAPI_ISteamUGC_SubmitItemUpdate(ugc, handle, std::ptr::null());
+        if call == sys::k_uAPICallInvalid {
+            return Err(format!("无法提交{}的标题和描述", translation.language));
+        }
+
+        let utils = sys::SteamAPI_SteamUtils_v010();
+        let started = Instant::now();
+        let mut failed = false;
+        while !sys::SteamAPI_ISteamUtils_IsAPICallCompleted(utils, call, &mut failed) {
+            if !running.load(Ordering::Relaxed) {
+                return Err(closed());
+            }
+            if started.elapsed() > TRANSLATION_TIMEOUT {
+                return Err(format!("提交{}的标题和描述超时", translation.language));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        let mut result: sys::SubmitItemUpdateResult_t = std::mem::zeroed();
+        let received = sys::SteamAPI_ISteamUtils_GetAPICallResult(
+            utils,
+            call,
+            (&mut result as *mut sys::SubmitItemUpdateResult_t).cast(),
+            std::mem::size_of::<sys::SubmitItemUpdateResult_t>() as _,
+            sys::SubmitItemUpdateResult_t_k_iCallback as _,
+            &mut failed,
+        );
+        if !received || failed {
+            return Err(format!("没有收到{}的提交结果", translation.language));
+        }
+        if result.m_eResult != sys::EResult::k_EResultOK {
+            return Err(format!(
+                "提交{}的标题和描述失败: {}",
+                translation.language,
+                SteamError::from(result.m_eResult)
+            ));
+        }
+    }
+    Ok(())
+}
+
+struct SteamWatch(UpdateWatchHandle);
+
+impl UpdateWatch for SteamWatch {
+    fn progress(&self) -> UpdateProgress {
+        let (status, uploaded, total) = self.0.progress();
+        let phase = match status {
+            UpdateStatus::Invalid => UpdatePhase::Invalid,
+            UpdateStatus::PreparingConfig => UpdatePhase::PreparingConfig,
+            UpdateStatus::PreparingContent => UpdatePhase::PreparingContent,
+            UpdateStatus::UploadingContent => UpdatePhase::UploadingContent,
+            UpdateStatus::UploadingPreviewFile => UpdatePhase::UploadingPreview,
+            UpdateStatus::CommittingChanges => UpdatePhase::Committing,
+        };
+        UpdateProgress {
+            phase,
+            uploaded,
+            total,
+        }
+    }
+}